@@ -0,0 +1,89 @@
+//! Throughput benchmarks for large boards
+//!
+//! Run with `cargo bench --bench board`
+
+use std::time::{Duration, Instant};
+
+use minesweeper::{minesweeper::*, solver::Solver};
+
+const WIDTH: u16 = 1000;
+const HEIGHT: u16 = 1000;
+const MINES: usize = 150_000;
+
+/// Print the throughput of a benchmark
+fn report(name: &str, count: usize, unit: &str, elapsed: Duration) {
+    let per_second = count as f64 / elapsed.as_secs_f64();
+    println!(
+        "{:<24} {:>10} {:<8} in {:>8.3}s ({:>12.0} {}/s)",
+        name,
+        count,
+        unit,
+        elapsed.as_secs_f64(),
+        per_second,
+        unit
+    );
+}
+
+fn bench_generation() {
+    let start = Instant::now();
+    let game = Minesweeper::new(WIDTH, HEIGHT, MINES);
+    report(
+        "generation",
+        usize::from(game.width) * usize::from(game.height),
+        "fields",
+        start.elapsed(),
+    );
+}
+
+fn bench_open() {
+    let mut game = Minesweeper::new(WIDTH, HEIGHT, MINES);
+
+    // Open every safe field, letting cascades open as much as they can
+    let start = Instant::now();
+    let mut opens = 0;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            if !game.is_mined((x, y)) && game.open((x, y)).is_some() {
+                opens += 1;
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    assert_eq!(game.game_state, GameState::Win);
+    report("open calls", opens, "opens", elapsed);
+    report(
+        "open + cascade",
+        usize::from(WIDTH) * usize::from(HEIGHT) - MINES,
+        "fields",
+        elapsed,
+    );
+}
+
+fn bench_solver_step() {
+    let mut game = Minesweeper::new(WIDTH, HEIGHT, MINES);
+
+    // Open the top half of the board so the solver has information to work with
+    for y in 0..HEIGHT / 2 {
+        for x in 0..WIDTH {
+            if !game.is_mined((x, y)) {
+                game.open((x, y));
+            }
+        }
+    }
+
+    let mut solver = Solver::new();
+    let steps = 3;
+    let start = Instant::now();
+    for _ in 0..steps {
+        solver.solve_step(&game);
+    }
+    report("solver step", steps, "steps", start.elapsed());
+}
+
+fn main() {
+    println!("Board size {}x{} with {} mines", WIDTH, HEIGHT, MINES);
+    bench_generation();
+    bench_open();
+    bench_solver_step();
+}
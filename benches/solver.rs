@@ -0,0 +1,61 @@
+//! Win rate of the solver strategies on seeded expert boards
+//!
+//! Run with `cargo bench --bench solver`, optionally followed by the number of games
+
+#![allow(clippy::needless_return)]
+
+use std::time::Instant;
+
+use minesweeper::{
+    minesweeper::*,
+    solver::{Solver, SolverStep, SolverStrategy},
+};
+
+const WIDTH: u16 = 30;
+const HEIGHT: u16 = 16;
+const MINES: usize = 99;
+const DEFAULT_GAMES: u64 = 500;
+
+/// Play a seeded game to the end, returning whether the solver won
+fn play(seed: u64, strategy: SolverStrategy) -> bool {
+    let mut game = Minesweeper::new_seeded(WIDTH, HEIGHT, MINES, seed)
+        .with_first_click(FirstClick::Safe)
+        .with_question_marks(false);
+    let mut solver = Solver::new().with_strategy(strategy);
+
+    while game.game_state == GameState::InProgress {
+        match solver.solve_step(&game) {
+            SolverStep::Open(pos) => {
+                game.open(pos);
+            }
+            SolverStep::Flag(pos) => game.flag(pos),
+            SolverStep::Unflag(pos) => game.clear_mark(pos),
+            SolverStep::None => break,
+        }
+    }
+    return game.game_state == GameState::Win;
+}
+
+fn main() {
+    // Cargo passes its own flags to benchmarks, only a plain number is the game count
+    let games = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(DEFAULT_GAMES);
+    println!(
+        "{} games on {}x{} boards with {} mines",
+        games, WIDTH, HEIGHT, MINES
+    );
+
+    for strategy in [SolverStrategy::SafestGuess, SolverStrategy::Guess] {
+        let start = Instant::now();
+        let wins = (0..games).filter(|&seed| play(seed, strategy)).count();
+        println!(
+            "{:<14} won {:>5} ({:>5.1}%) in {:>7.2}s",
+            strategy.to_string(),
+            wins,
+            wins as f64 / games as f64 * 100.0,
+            start.elapsed().as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Post-mortem of a finished game, replaying the player's moves with the solver
+//!
+//! Every move is reviewed against the board the player saw at the time: how likely it was to go wrong,
+//! whether a field was certain to be safe instead, and so whether a risky move was a forced guess or an
+//! avoidable mistake.
+
+use std::{
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{minesweeper::*, solver::Solver};
+
+/// Everything needed to replay a game: the board, its rules and the player's moves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub width: u16,
+    pub height: u16,
+    /// Final positions of the mines, after any were moved away from the first click
+    pub mines: Vec<Position>,
+    pub lives: u8,
+    pub two_player: bool,
+    pub question_marks: bool,
+    pub first_click: FirstClick,
+    pub moves: Vec<Move>,
+}
+
+impl GameRecord {
+    /// Record the board and every move made so far
+    pub fn from_game(game: &Minesweeper) -> GameRecord {
+        return GameRecord {
+            width: game.width,
+            height: game.height,
+            mines: game.mine_positions(),
+            lives: game.starting_lives(),
+            two_player: game.is_two_player(),
+            question_marks: game.has_question_marks(),
+            first_click: game.first_click(),
+            moves: game.moves().to_vec(),
+        };
+    }
+
+    /// Create the game as it was before the first move
+    ///
+    /// The mines are already in their final place, so the first click is not protected again
+    pub fn new_game(&self) -> Minesweeper {
+        let game = Minesweeper::from_mines(self.width, self.height, &self.mines)
+            .with_lives(self.lives)
+            .with_question_marks(self.question_marks);
+        if self.two_player {
+            return game.with_two_players();
+        }
+        return game;
+    }
+
+    /// Location of the last finished game in the user's data directory
+    pub fn path() -> Option<PathBuf> {
+        return dirs::data_dir().map(|dir| dir.join("minesweeper").join("last_game.toml"));
+    }
+
+    /// Read a record written by [GameRecord::save]
+    pub fn load(path: &Path) -> Result<GameRecord, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        return toml::from_str(&contents).map_err(|error| error.to_string());
+    }
+
+    /// Write the record, creating the directory if needed
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = toml::to_string(self).map_err(|error| error.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+        }
+        return fs::write(path, contents).map_err(|error| error.to_string());
+    }
+}
+
+/// Apply a recorded move to the game
+pub fn apply_move(game: &mut Minesweeper, player_move: Move) -> Result<(), MinesweeperError> {
+    let pos = player_move.pos;
+    match player_move.kind {
+        MoveKind::Open => game.try_open(pos).map(|_| ()),
+        MoveKind::Flag => game.try_flag(pos),
+        MoveKind::Unflag => game.try_clear_mark(pos),
+        MoveKind::Chord => game.try_chord(pos).map(|_| ()),
+    }
+}
+
+/// How a move compares to what the board allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The numbers showed the move could not go wrong
+    Certain,
+    /// No field was certain to be safe, so a guess was needed
+    ForcedGuess,
+    /// A field was certain to be safe, but the player took a risk instead
+    AvoidableGuess,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Certain => f.write_str("certain"),
+            Verdict::ForcedGuess => f.write_str("forced guess"),
+            Verdict::AvoidableGuess => f.write_str("avoidable guess"),
+        }
+    }
+}
+
+/// Review of a single move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveReview {
+    pub player_move: Move,
+    /// Chance the move went wrong: opening a mine, or flagging a safe field
+    pub risk: f32,
+    /// First field in row order that was certain to be safe, if any
+    pub safe_field: Option<Position>,
+    pub verdict: Verdict,
+    /// Whether the move opened a mine
+    pub hit_mine: bool,
+}
+
+impl Display for MoveReview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {:.0}% {}",
+            self.player_move,
+            self.risk * 100.0,
+            self.verdict
+        )?;
+        if let (Verdict::AvoidableGuess, Some(pos)) = (self.verdict, self.safe_field) {
+            write!(f, ", ({}, {}) was safe", pos.0, pos.1)?;
+        }
+        if self.hit_mine {
+            f.write_str(", hit a mine")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Review of every move of a game
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    pub moves: Vec<MoveReview>,
+    /// State the game ended in
+    pub state: GameState,
+}
+
+impl Review {
+    /// Number of moves with the given verdict
+    pub fn count(&self, verdict: Verdict) -> usize {
+        return self
+            .moves
+            .iter()
+            .filter(|review| review.verdict == verdict)
+            .count();
+    }
+
+    /// One line describing the outcome and the verdicts
+    pub fn summary(&self) -> String {
+        let state = match self.state {
+            GameState::Win => "Won",
+            GameState::Loss => "Lost",
+            GameState::InProgress => "Unfinished",
+        };
+        return format!(
+            "{} after {} moves: {} certain, {} forced guesses, {} avoidable guesses",
+            state,
+            self.moves.len(),
+            self.count(Verdict::Certain),
+            self.count(Verdict::ForcedGuess),
+            self.count(Verdict::AvoidableGuess)
+        );
+    }
+}
+
+impl Display for Review {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, review) in self.moves.iter().enumerate() {
+            writeln!(f, "{:>4}. {}", i + 1, review)?;
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+/// Replay a game, reviewing every move against the board as it was before the move
+pub fn review(record: &GameRecord) -> Review {
+    let mut game = record.new_game();
+    let mut solver = Solver::new();
+    let mut moves = Vec::new();
+
+    for &player_move in &record.moves {
+        solver.calculate_field(&game);
+        let chance = |pos: Position| solver.get_mine_chance(pos).value();
+        let is_closed =
+            |pos: Position| !game.is_open(pos) && !game.is_detonated(pos) && !game.is_flagged(pos);
+
+        let safe_field = (0..game.height)
+            .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+            .find(|&pos| is_closed(pos) && chance(pos) <= 0.0);
+
+        let pos = player_move.pos;
+        let risk = match player_move.kind {
+            // A protected first click cannot go wrong, whatever the board looked like
+            MoveKind::Open
+                if !game.has_started() && record.first_click != FirstClick::Unprotected =>
+            {
+                0.0
+            }
+            MoveKind::Open => chance(pos),
+            MoveKind::Chord => game
+                .neighboring_fields_iter(pos)
+                .filter(|&neighbor| is_closed(neighbor) && !game.is_question(neighbor))
+                .map(chance)
+                .fold(0.0, f32::max),
+            MoveKind::Flag if !game.is_flagged(pos) && !game.is_question(pos) => 1.0 - chance(pos),
+            MoveKind::Flag | MoveKind::Unflag => 0.0,
+        };
+        let verdict = if risk <= 0.0 {
+            Verdict::Certain
+        } else if safe_field.is_some() {
+            Verdict::AvoidableGuess
+        } else {
+            Verdict::ForcedGuess
+        };
+
+        if let Err(error) = apply_move(&mut game, player_move) {
+            log::warn!("Recorded move '{}' is not valid: {}", player_move, error);
+        }
+        let hit_mine = game.drain_events().iter().any(|event| {
+            matches!(
+                event,
+                GameEvent::MineDetonated(_) | GameEvent::MineClaimed(_, _)
+            )
+        });
+
+        moves.push(MoveReview {
+            player_move,
+            risk,
+            safe_field,
+            verdict,
+            hit_mine,
+        });
+    }
+
+    return Review {
+        moves,
+        state: game.game_state,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn review_test() {
+        let mut game = Minesweeper::from_mines(3, 3, &[(0, 2), (2, 2)]);
+        // No information yet, then a flag the numbers prove, then a mine with (1, 2) known to be safe
+        game.open((0, 0));
+        game.flag((0, 2));
+        game.open((2, 2));
+        assert_eq!(game.game_state, GameState::Loss);
+
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.moves.len(), 3);
+        let review = review(&record);
+        assert_eq!(review.state, GameState::Loss);
+
+        let verdicts: Vec<Verdict> = review.moves.iter().map(|review| review.verdict).collect();
+        assert_eq!(
+            verdicts,
+            [
+                Verdict::ForcedGuess,
+                Verdict::Certain,
+                Verdict::AvoidableGuess
+            ]
+        );
+        assert!((review.moves[0].risk - 2.0 / 9.0).abs() < 1e-6);
+        assert_eq!(review.moves[2].risk, 1.0);
+        assert_eq!(review.moves[2].safe_field, Some((1, 2)));
+        assert!(review.moves[2].hit_mine && !review.moves[0].hit_mine);
+        assert_eq!(
+            review.moves[2].to_string(),
+            "Open (2, 2) 100% avoidable guess, (1, 2) was safe, hit a mine"
+        );
+        assert_eq!(
+            review.summary(),
+            "Lost after 3 moves: 1 certain, 1 forced guesses, 1 avoidable guesses"
+        );
+    }
+
+    #[test]
+    fn record_test() {
+        // Mines moved away from the first click are recorded where they ended up
+        let mut game = Minesweeper::new_seeded(9, 9, 10, 4).with_first_click(FirstClick::Opening);
+        game.open((4, 4));
+        game.flag((0, 0));
+        game.flag((0, 0));
+
+        let record = GameRecord::from_game(&game);
+        let parsed: GameRecord = toml::from_str(&toml::to_string(&record).unwrap()).unwrap();
+        assert_eq!(parsed, record);
+
+        let mut replayed = record.new_game();
+        for &player_move in &record.moves {
+            apply_move(&mut replayed, player_move).unwrap();
+        }
+        for y in 0..game.height {
+            for x in 0..game.width {
+                assert_eq!(
+                    replayed.get_field_state((x, y)),
+                    game.get_field_state((x, y))
+                );
+            }
+        }
+
+        // The protected first click is never a risk
+        assert_eq!(review(&record).moves[0].verdict, Verdict::Certain);
+    }
+}
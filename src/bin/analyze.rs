@@ -0,0 +1,31 @@
+//! Print the post-mortem of a recorded game, see [minesweeper::analysis]
+//!
+//! Run with `cargo run --bin analyze -- [record file]`, defaulting to the last game finished in the user interface
+
+#![allow(clippy::needless_return)]
+
+use std::path::PathBuf;
+
+use minesweeper::analysis::{self, GameRecord};
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => PathBuf::from(path),
+        None => match GameRecord::path() {
+            Some(path) => path,
+            None => {
+                eprintln!("No data directory, pass the record file to analyze");
+                std::process::exit(2);
+            }
+        },
+    };
+
+    let record = match GameRecord::load(&path) {
+        Ok(record) => record,
+        Err(error) => {
+            eprintln!("Could not read '{}': {}", path.display(), error);
+            std::process::exit(1);
+        }
+    };
+    println!("{}", analysis::review(&record));
+}
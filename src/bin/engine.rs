@@ -0,0 +1,298 @@
+//! Headless engine, plays seeded games against a bot speaking the protocol in [minesweeper::protocol]
+//!
+//! Run with `cargo run --bin engine -- [options] -- <bot command> [args...]`
+
+#![allow(clippy::needless_return)]
+
+use std::{
+    io::{BufReader, Write},
+    process::{Child, Command, Stdio},
+};
+
+use minesweeper::{
+    minesweeper::*,
+    protocol::{self, GameReport, Host},
+    solver::{Solver, SolverStep},
+};
+use simple_logger::SimpleLogger;
+
+const USAGE: &str = "\
+Usage: engine [options] -- <bot command> [args...]
+       engine [options] --solver
+
+Plays seeded games against a bot, talking to it over its stdin and stdout.
+
+Options:
+  --games <n>          Number of games to play (default 10)
+  --seed <n>           Seed of the first game, each game after uses the next seed (default 0)
+  --size <w>x<h>       Board size (default 30x16)
+  --mines <n>          Number of mines (default 99)
+  --lives <n>          Mines that can be hit before losing (default 1)
+  --first-click <rule> unprotected, safe or opening (default safe)
+  --max-moves <n>      Moves before a game is abandoned (default four per field)
+  --solver             Play with the built-in solver instead of a bot process";
+
+/// Where moves come from
+enum Player {
+    Bot(Vec<String>),
+    Solver,
+}
+
+struct Options {
+    games: u64,
+    seed: u64,
+    width: u16,
+    height: u16,
+    mines: usize,
+    lives: u8,
+    first_click: FirstClick,
+    max_moves: Option<usize>,
+    player: Player,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            seed: 0,
+            width: 30,
+            height: 16,
+            mines: 99,
+            lives: 1,
+            first_click: FirstClick::Safe,
+            max_moves: None,
+            player: Player::Solver,
+        }
+    }
+}
+
+/// Parse the command line, returning a message to print on failure
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut player = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            let command: Vec<String> = args.by_ref().collect();
+            if command.is_empty() {
+                return Err(String::from("Missing bot command after '--'"));
+            }
+            if matches!(player, Some(Player::Solver)) {
+                return Err(String::from(
+                    "Use either '--solver' or a bot command, not both",
+                ));
+            }
+            player = Some(Player::Bot(command));
+            break;
+        }
+        if arg == "--solver" {
+            player = Some(Player::Solver);
+            continue;
+        }
+        if arg == "--help" || arg == "-h" {
+            return Err(String::from(USAGE));
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for '{}'", arg))?;
+        let invalid = || format!("Invalid value '{}' for '{}'", value, arg);
+        match arg.as_str() {
+            "--games" => options.games = value.parse().map_err(|_| invalid())?,
+            "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+            "--mines" => options.mines = value.parse().map_err(|_| invalid())?,
+            "--lives" => options.lives = value.parse().map_err(|_| invalid())?,
+            "--max-moves" => options.max_moves = Some(value.parse().map_err(|_| invalid())?),
+            "--size" => {
+                let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                options.width = width.parse().map_err(|_| invalid())?;
+                options.height = height.parse().map_err(|_| invalid())?;
+            }
+            "--first-click" => {
+                options.first_click = match value.as_str() {
+                    "unprotected" => FirstClick::Unprotected,
+                    "safe" => FirstClick::Safe,
+                    "opening" => FirstClick::Opening,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    options.player = player.ok_or_else(|| String::from(USAGE))?;
+    return Ok(options);
+}
+
+/// Play a game with the built-in solver
+fn play_solver(game: &mut Minesweeper, max_moves: usize) -> GameReport {
+    let mut solver = Solver::new();
+    let mut report = GameReport {
+        state: game.game_state,
+        moves: 0,
+        invalid_moves: 0,
+    };
+
+    while game.game_state == GameState::InProgress && report.moves < max_moves {
+        let step = solver.solve_step(game);
+        if step == SolverStep::None {
+            break;
+        }
+        report.moves += 1;
+        if protocol::apply_move(game, step).is_err() {
+            report.invalid_moves += 1;
+        }
+    }
+    game.drain_events();
+
+    report.state = game.game_state;
+    return report;
+}
+
+/// Start a bot process with its stdin and stdout piped to the engine
+fn spawn_bot(command: &[String]) -> Result<Child, String> {
+    return Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("Could not start bot '{}': {}", command[0], error));
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut bot = match &options.player {
+        Player::Bot(command) => {
+            let mut child = spawn_bot(command)?;
+            let stdin = child.stdin.take().expect("Bot stdin is piped");
+            let stdout = BufReader::new(child.stdout.take().expect("Bot stdout is piped"));
+            let mut host = Host::new(stdout, stdin);
+            host.handshake().map_err(|error| error.to_string())?;
+            Some((child, host))
+        }
+        Player::Solver => None,
+    };
+
+    let (mut wins, mut losses): (u64, u64) = (0, 0);
+    let (mut total_moves, mut total_invalid) = (0, 0);
+    // Seeds near the largest wrap around to zero, as in rating::find_board
+    for seed in (0..options.games).map(|game| options.seed.wrapping_add(game)) {
+        let mut game =
+            Minesweeper::try_new_seeded(options.width, options.height, options.mines, seed)
+                .map_err(|error| error.to_string())?
+                .with_lives(options.lives)
+                .with_first_click(options.first_click);
+        let max_moves = options
+            .max_moves
+            .unwrap_or(4 * usize::from(game.width) * usize::from(game.height));
+
+        let report = match &mut bot {
+            Some((_, host)) => host
+                .play(&mut game, max_moves)
+                .map_err(|error| format!("Game with seed {} failed: {}", seed, error))?,
+            None => play_solver(&mut game, max_moves),
+        };
+
+        let result = match report.state {
+            GameState::Win => "win",
+            GameState::Loss => "loss",
+            GameState::InProgress => "unfinished",
+        };
+        println!(
+            "seed {:>6}: {:<10} {:>5} moves, {} invalid",
+            seed, result, report.moves, report.invalid_moves
+        );
+
+        match report.state {
+            GameState::Win => wins += 1,
+            GameState::Loss => losses += 1,
+            GameState::InProgress => {}
+        }
+        total_moves += report.moves;
+        total_invalid += report.invalid_moves;
+    }
+
+    if let Some((mut child, mut host)) = bot {
+        host.quit().map_err(|error| error.to_string())?;
+        drop(host);
+        if let Err(error) = child.wait() {
+            log::warn!("Bot did not exit cleanly: {}", error);
+        }
+    }
+
+    let games = options.games.max(1) as f64;
+    println!(
+        "Won {} of {} games ({:.1}%), lost {}, unfinished {}",
+        wins,
+        options.games,
+        wins as f64 / games * 100.0,
+        losses,
+        options.games - wins - losses
+    );
+    println!(
+        "Average {:.1} moves per game, {} invalid moves in total",
+        total_moves as f64 / games,
+        total_invalid
+    );
+    std::io::stdout().flush().ok();
+    return Ok(());
+}
+
+fn main() {
+    SimpleLogger::new()
+        .with_level(log::LevelFilter::Warn)
+        .env()
+        .init()
+        .unwrap();
+
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(message) = run(&options) {
+        eprintln!("{}", message);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        return parse_args(args.iter().map(|arg| arg.to_string()));
+    }
+
+    #[test]
+    fn parse_args_test() {
+        let options = parse(&["--size", "16x8", "--mines", "20", "--solver"]).unwrap();
+        assert_eq!((options.width, options.height, options.mines), (16, 8, 20));
+        assert!(matches!(options.player, Player::Solver));
+
+        let options = parse(&["--seed", "18446744073709551615", "--", "bot", "--fast"]).unwrap();
+        assert_eq!(options.seed, u64::MAX);
+        assert!(matches!(&options.player, Player::Bot(command) if command == &["bot", "--fast"]));
+
+        assert_eq!(
+            parse(&["--size", "16", "--solver"]).err().unwrap(),
+            "Invalid value '16' for '--size'"
+        );
+        assert_eq!(
+            parse(&["--solver", "--games"]).err().unwrap(),
+            "Missing value for '--games'"
+        );
+        assert_eq!(
+            parse(&["--solver", "--", "bot"]).err().unwrap(),
+            "Use either '--solver' or a bot command, not both"
+        );
+        assert_eq!(
+            parse(&["--",]).err().unwrap(),
+            "Missing bot command after '--'"
+        );
+        assert_eq!(parse(&[]).err().unwrap(), USAGE);
+    }
+}
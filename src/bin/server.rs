@@ -0,0 +1,40 @@
+//! Local HTTP/JSON API for controlling games, see [minesweeper::server]
+//!
+//! Run with `cargo run --bin server -- [port]`
+
+#![allow(clippy::needless_return)]
+
+use minesweeper::server::Server;
+use simple_logger::SimpleLogger;
+
+const DEFAULT_PORT: u16 = 7878;
+
+fn main() {
+    SimpleLogger::new()
+        .with_level(log::LevelFilter::Info)
+        .env()
+        .init()
+        .unwrap();
+
+    let port = match std::env::args().nth(1).map(|port| port.parse::<u16>()) {
+        None => DEFAULT_PORT,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => {
+            eprintln!("Usage: server [port]");
+            std::process::exit(2);
+        }
+    };
+
+    // Only listen locally, the API has no authentication
+    let server = match Server::bind(("127.0.0.1", port)) {
+        Ok(server) => server,
+        Err(error) => {
+            eprintln!("Could not listen on port {}: {}", port, error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(addr) = server.local_addr() {
+        log::info!("Listening on http://{}", addr);
+    }
+    server.run();
+}
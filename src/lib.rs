@@ -0,0 +1,11 @@
+#![allow(clippy::needless_return)]
+
+pub mod analysis;
+pub mod minesweeper;
+#[cfg(test)]
+mod properties;
+pub mod protocol;
+pub mod race;
+pub mod server;
+pub mod solver;
+pub mod user_interface;
@@ -1,19 +1,18 @@
-mod minesweeper;
-mod solver;
-mod user_interface;
-
 use iced::{
     window::{icon, Settings},
     Font,
 };
+use minesweeper::user_interface::{self, *};
 use simple_logger::SimpleLogger;
-use user_interface::*;
 
 fn main() -> iced::Result {
+    // Loaded once and handed to the interface
+    let preferences = settings::Settings::load();
+
     // Configure logging
     SimpleLogger::new()
         .with_colors(true)
-        .with_level(log::LevelFilter::Info)
+        .with_level(preferences.log_level)
         .with_local_timestamps()
         .init()
         .unwrap();
@@ -30,6 +29,10 @@ fn main() -> iced::Result {
         ..Default::default()
     };
 
+    // The window is sized for the interface it will show, so only one is created
+    let (interface, task) = MinesweeperInterface::new(preferences);
+    let window_size = interface.calculate_size();
+
     iced::application(
         MinesweeperInterface::title,
         MinesweeperInterface::update,
@@ -38,9 +41,9 @@ fn main() -> iced::Result {
     .window(settings)
     .subscription(MinesweeperInterface::subscription)
     .scale_factor(MinesweeperInterface::scale_factor)
-    .resizable(false)
+    .resizable(true)
     .default_font(Font::MONOSPACE)
     .antialiasing(false)
-    .window_size(MinesweeperInterface::default().calculate_size())
-    .run()
+    .window_size(window_size)
+    .run_with(move || (interface, task))
 }
@@ -1,15 +1,34 @@
-use rand::prelude::*;
+use rand::{
+    rngs::StdRng,
+    seq::{index, IndexedRandom},
+    SeedableRng,
+};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, VecDeque},
     fmt::{Display, Write},
+    sync::atomic::{AtomicU64, Ordering},
 };
 
 pub type Position = (u16, u16);
 
+// Bit flags describing a single field in the board grid
+const MINE: u8 = 1 << 0;
+const OPEN: u8 = 1 << 1;
+const FLAG: u8 = 1 << 2;
+const QUESTION: u8 = 1 << 3;
+const DETONATED: u8 = 1 << 4;
+
+/// Id of the next game created, see [Minesweeper::id]
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
 /// Result of opening a minesweeper field
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OpenResult {
     Mine,
     NoMine(u8),
+    /// Opening spread to neighboring fields, contains every field opened in the order they were opened
+    Cascade(Vec<Position>),
 }
 
 /// Display implementation for [OpenResult]
@@ -21,13 +40,158 @@ impl Display for OpenResult {
         if let &OpenResult::NoMine(count) = self {
             write!(f, "NoMine({})", count)?;
         }
+        if let OpenResult::Cascade(opened) = self {
+            write!(f, "Cascade({} fields)", opened.len())?;
+        }
 
         Ok(())
     }
 }
 
+/// Errors returned by invalid game operations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinesweeperError {
+    /// The board has no fields
+    EmptyBoard,
+    /// There are not enough fields to place the mines and leave at least one safe field
+    TooManyMines {
+        mines: usize,
+        fields: usize,
+    },
+    OutOfBounds(Position),
+    AlreadyOpen(Position),
+    /// The field is flagged or marked with a question mark, so cannot be opened
+    Marked(Position),
+    /// The field is a mine the player already hit
+    Detonated(Position),
+    /// The field must be open, e.g. to chord it
+    NotOpen(Position),
+    /// The field has no flag or question mark to clear
+    NotMarked(Position),
+    /// The game has already been won or lost
+    GameOver,
+}
+
+/// Display implementation for [MinesweeperError]
+impl Display for MinesweeperError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MinesweeperError::EmptyBoard => f.write_str("Board has no fields"),
+            MinesweeperError::TooManyMines { mines, fields } => write!(
+                f,
+                "Cannot place {} mines on a board with {} fields",
+                mines, fields
+            ),
+            MinesweeperError::OutOfBounds(pos) => {
+                write!(f, "Field ({}, {}) is out of bounds", pos.0, pos.1)
+            }
+            MinesweeperError::AlreadyOpen(pos) => {
+                write!(f, "Field ({}, {}) is already open", pos.0, pos.1)
+            }
+            MinesweeperError::Marked(pos) => {
+                write!(f, "Field ({}, {}) is marked", pos.0, pos.1)
+            }
+            MinesweeperError::Detonated(pos) => {
+                write!(f, "Field ({}, {}) is a detonated mine", pos.0, pos.1)
+            }
+            MinesweeperError::NotOpen(pos) => {
+                write!(f, "Field ({}, {}) is not open", pos.0, pos.1)
+            }
+            MinesweeperError::NotMarked(pos) => {
+                write!(f, "Field ({}, {}) is not marked", pos.0, pos.1)
+            }
+            MinesweeperError::GameOver => f.write_str("Game is over"),
+        }
+    }
+}
+
+impl std::error::Error for MinesweeperError {}
+
+/// Events emitted by the game as the board changes
+///
+/// Events are queued until collected with [Minesweeper::drain_events]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A single field was opened, with the number of neighboring mines
+    Opened(Position, u8),
+    /// Opening a field spread to its neighbors, contains every field opened in order
+    Cascade(Vec<Position>),
+    FlagPlaced(Position),
+    FlagRemoved(Position),
+    QuestionPlaced(Position),
+    QuestionRemoved(Position),
+    /// The player opened a mine, followed by [GameEvent::Lost] if it was their last life
+    MineDetonated(Position),
+    /// In a two player game, the given player found a mine and scored a point
+    MineClaimed(Position, usize),
+    /// In a two player game, it is now the given player's turn
+    TurnChanged(usize),
+    Won,
+    Lost,
+}
+
+/// Kind of move made by the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveKind {
+    Open,
+    /// Cycle the mark on a field
+    Flag,
+    /// Clear the mark on a field
+    Unflag,
+    Chord,
+}
+
+impl Display for MoveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveKind::Open => f.write_str("Open"),
+            MoveKind::Flag => f.write_str("Flag"),
+            MoveKind::Unflag => f.write_str("Unflag"),
+            MoveKind::Chord => f.write_str("Chord"),
+        }
+    }
+}
+
+/// A move accepted by the game, recorded in [Minesweeper::moves]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub kind: MoveKind,
+    pub pos: Position,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}, {})", self.kind, self.pos.0, self.pos.1)
+    }
+}
+
+/// Protection given to the first field the player opens
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FirstClick {
+    /// The first field may be a mine
+    #[default]
+    Unprotected,
+    /// The first field is never a mine
+    Safe,
+    /// The first field and its neighbors are never mines, so the first open always cascades
+    Opening,
+}
+
+impl Display for FirstClick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirstClick::Unprotected => f.write_str("Unprotected"),
+            FirstClick::Safe => f.write_str("Safe"),
+            FirstClick::Opening => f.write_str("Opening"),
+        }
+    }
+}
+
 /// Enum listing the possible states of the game
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GameState {
     InProgress,
     Win,
@@ -35,6 +199,7 @@ pub enum GameState {
 }
 
 /// Enum listing the possible states of a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FieldState {
     Unknown,
     MineRevealed,
@@ -43,6 +208,8 @@ pub enum FieldState {
     Open(u8),
     Flagged,
     Question,
+    /// Mine found by the given player in a two player game
+    Claimed(usize),
 }
 
 /// Minesweeper game implementation
@@ -51,51 +218,274 @@ pub struct Minesweeper {
     pub width: u16,
     pub height: u16,
     pub game_state: GameState,
-    opened: HashSet<Position>,
-    flagged: HashSet<Position>,
-    question: HashSet<Position>,
-    mines: HashSet<Position>,
+    id: u64,
+    /// Bit flags for every field, stored row by row
+    fields: Vec<u8>,
+    /// Precomputed number of mines surrounding every field
+    mine_counts: Vec<u8>,
+    num_mines: usize,
+    num_opened: usize,
+    num_flagged: usize,
+    num_detonated: usize,
+    lives: u8,
+    starting_lives: u8,
+    first_click: FirstClick,
+    question_marks: bool,
+    events: Vec<GameEvent>,
+    /// Source of randomness once the mines are placed, seeded games stay reproducible
+    rng: StdRng,
+    /// Mines found by each player, empty unless this is a two player game
+    scores: Vec<usize>,
+    current_player: usize,
+    /// Player who found each claimed mine
+    claims: HashMap<Position, usize>,
+    /// Every move accepted so far, oldest first
+    moves: Vec<Move>,
 }
 
 impl Minesweeper {
+    /// Create a game with randomly placed mines
+    ///
+    /// Panics if the board is invalid, see [Minesweeper::try_new]
     pub fn new(width: u16, height: u16, num_mines: usize) -> Minesweeper {
-        Minesweeper {
+        return Self::try_new(width, height, num_mines).expect("Invalid board");
+    }
+
+    /// Create a game with randomly placed mines, rejecting boards that cannot be played
+    pub fn try_new(
+        width: u16,
+        height: u16,
+        num_mines: usize,
+    ) -> Result<Minesweeper, MinesweeperError> {
+        return Self::generate(width, height, num_mines, StdRng::from_rng(&mut rand::rng()));
+    }
+
+    /// Create a game with mines placed from the given seed
+    ///
+    /// Panics if the board is invalid, see [Minesweeper::try_new_seeded]
+    pub fn new_seeded(width: u16, height: u16, num_mines: usize, seed: u64) -> Minesweeper {
+        return Self::try_new_seeded(width, height, num_mines, seed).expect("Invalid board");
+    }
+
+    /// Create a game with mines placed from the given seed, rejecting boards that cannot be played
+    ///
+    /// The same seed always gives the same game, including mines moved by first click protection
+    pub fn try_new_seeded(
+        width: u16,
+        height: u16,
+        num_mines: usize,
+        seed: u64,
+    ) -> Result<Minesweeper, MinesweeperError> {
+        return Self::generate(width, height, num_mines, StdRng::seed_from_u64(seed));
+    }
+
+    fn generate(
+        width: u16,
+        height: u16,
+        num_mines: usize,
+        mut rng: StdRng,
+    ) -> Result<Minesweeper, MinesweeperError> {
+        let area = usize::from(width) * usize::from(height);
+        if area == 0 {
+            return Err(MinesweeperError::EmptyBoard);
+        }
+        if num_mines >= area {
+            return Err(MinesweeperError::TooManyMines {
+                mines: num_mines,
+                fields: area,
+            });
+        }
+
+        let mines: Vec<Position> = index::sample(&mut rng, area, num_mines)
+            .into_iter()
+            .map(|i| {
+                (
+                    (i % usize::from(width)) as u16,
+                    (i / usize::from(width)) as u16,
+                )
+            })
+            .collect();
+
+        let mut game = Self::from_mines(width, height, &mines);
+        game.rng = rng;
+        return Ok(game);
+    }
+
+    /// Create a game with mines at the given positions
+    ///
+    /// Positions out of bounds are ignored
+    pub fn from_mines(width: u16, height: u16, mines: &[Position]) -> Minesweeper {
+        let area = usize::from(width) * usize::from(height);
+        let mut game = Minesweeper {
             width,
             height,
-            opened: HashSet::new(),
-            flagged: HashSet::new(),
-            question: HashSet::new(),
-            mines: {
-                let mut mines = HashSet::new();
-                let mut rng = rand::rng();
-
-                while mines.len() < num_mines {
-                    let pos = (rng.random_range(0..width), rng.random_range(0..height));
-                    mines.insert(pos);
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            fields: vec![0; area],
+            mine_counts: vec![0; area],
+            num_mines: 0,
+            num_opened: 0,
+            num_flagged: 0,
+            num_detonated: 0,
+            lives: 1,
+            starting_lives: 1,
+            first_click: FirstClick::Unprotected,
+            question_marks: true,
+            events: Vec::new(),
+            rng: StdRng::from_rng(&mut rand::rng()),
+            scores: Vec::new(),
+            current_player: 0,
+            claims: HashMap::new(),
+            moves: Vec::new(),
+            game_state: GameState::InProgress,
+        };
+
+        for &pos in mines {
+            if let Some(i) = game.index(pos) {
+                if game.fields[i] & MINE == 0 {
+                    game.fields[i] |= MINE;
+                    game.num_mines += 1;
                 }
+            }
+        }
 
-                mines
-            },
-            game_state: GameState::InProgress,
+        // Precompute neighbor counts so the board never has to be scanned again
+        for y in 0..height {
+            for x in 0..width {
+                let count = game
+                    .neighboring_fields_iter((x, y))
+                    .filter(|&pos| game.is_mined(pos))
+                    .count() as u8;
+                game.mine_counts[usize::from(y) * usize::from(width) + usize::from(x)] = count;
+            }
+        }
+
+        return game;
+    }
+
+    /// Set the number of mines the player can hit before losing the game
+    ///
+    /// With more than one life, an opened mine is marked as detonated instead of ending the game
+    pub fn with_lives(mut self, lives: u8) -> Minesweeper {
+        self.lives = lives.max(1);
+        self.starting_lives = self.lives;
+        self
+    }
+
+    /// Make this a two player "Flags" game
+    ///
+    /// Players take turns, finding a mine scores a point and gives another turn, opening a safe field passes the turn.
+    /// The game ends once one player has found more than half of the mines, lives are not used.
+    pub fn with_two_players(mut self) -> Minesweeper {
+        self.scores = vec![0, 0];
+        self.current_player = 0;
+        self.lives = 1;
+        self.starting_lives = 1;
+        self
+    }
+
+    /// Set the protection given to the first field the player opens
+    pub fn with_first_click(mut self, first_click: FirstClick) -> Minesweeper {
+        self.first_click = first_click;
+        self
+    }
+
+    /// Set whether marking a flagged field again turns it into a question mark
+    pub fn with_question_marks(mut self, question_marks: bool) -> Minesweeper {
+        self.set_question_marks(question_marks);
+        self
+    }
+
+    /// Check if marking a flagged field again turns it into a question mark
+    pub fn has_question_marks(&self) -> bool {
+        return self.question_marks;
+    }
+
+    /// Change whether marks cycle through question marks, clearing any question marks when disabled
+    pub fn set_question_marks(&mut self, question_marks: bool) {
+        self.question_marks = question_marks;
+        if question_marks {
+            return;
+        }
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.is_question((x, y)) {
+                    self.unset((x, y), QUESTION);
+                    self.events.push(GameEvent::QuestionRemoved((x, y)));
+                }
+            }
+        }
+    }
+
+    // region Field storage
+
+    /// Get the index of the given position in the field grid
+    fn index(&self, pos: Position) -> Option<usize> {
+        if !self.is_in_bounds(pos) {
+            return None;
+        }
+        return Some(usize::from(pos.1) * usize::from(self.width) + usize::from(pos.0));
+    }
+
+    fn has(&self, pos: Position, flag: u8) -> bool {
+        match self.index(pos) {
+            Some(i) => self.fields[i] & flag != 0,
+            None => false,
+        }
+    }
+
+    fn set(&mut self, pos: Position, flag: u8) {
+        if let Some(i) = self.index(pos) {
+            self.fields[i] |= flag;
+        }
+    }
+
+    fn unset(&mut self, pos: Position, flag: u8) {
+        if let Some(i) = self.index(pos) {
+            self.fields[i] &= !flag;
+        }
+    }
+
+    /// Move a mine to another field, keeping the neighbor counts up to date
+    pub(crate) fn move_mine(&mut self, from: Position, to: Position) {
+        self.unset(from, MINE);
+        for neighbor in self.neighboring_fields_iter(from) {
+            if let Some(i) = self.index(neighbor) {
+                self.mine_counts[i] -= 1;
+            }
+        }
+
+        self.set(to, MINE);
+        for neighbor in self.neighboring_fields_iter(to) {
+            if let Some(i) = self.index(neighbor) {
+                self.mine_counts[i] += 1;
+            }
         }
     }
 
+    // endregion
+
     // region Position checks
 
     pub fn is_mined(&self, pos: Position) -> bool {
-        self.mines.contains(&pos)
+        self.has(pos, MINE)
     }
 
     pub fn is_open(&self, pos: Position) -> bool {
-        self.opened.contains(&pos)
+        self.has(pos, OPEN)
     }
 
     pub fn is_flagged(&self, pos: Position) -> bool {
-        self.flagged.contains(&pos)
+        self.has(pos, FLAG)
     }
 
     pub fn is_question(&self, pos: Position) -> bool {
-        self.question.contains(&pos)
+        self.has(pos, QUESTION)
+    }
+
+    /// Check if the field is a mine that was hit while the player still had lives to spare
+    pub fn is_detonated(&self, pos: Position) -> bool {
+        self.has(pos, DETONATED)
     }
 
     pub fn is_in_bounds(&self, pos: Position) -> bool {
@@ -104,20 +494,30 @@ impl Minesweeper {
 
     /// Get the state of the field with the given position
     pub fn get_field_state(&self, pos: Position) -> FieldState {
+        // Found mines stay with the player who found them
+        if let Some(&player) = self.claims.get(&pos) {
+            return FieldState::Claimed(player);
+        }
+
         if self.game_state == GameState::InProgress {
+            // Show mines the player has already hit
+            if self.is_detonated(pos) {
+                return FieldState::MineDetonated;
+            }
+
             // Show flagged field
             if self.is_flagged(pos) {
                 return FieldState::Flagged;
             }
 
-            // Show question field
-            if self.is_question(pos) {
+            // Show question field, only reachable while question marks are enabled
+            if self.question_marks && self.is_question(pos) {
                 return FieldState::Question;
             }
         } else {
             if self.is_mined(pos) {
                 // Player opened a mine, whoops
-                if self.is_open(pos) {
+                if self.is_open(pos) || self.is_detonated(pos) {
                     return FieldState::MineDetonated;
                 }
                 return FieldState::MineRevealed;
@@ -147,15 +547,26 @@ impl Minesweeper {
     // region Win/loss condition checks
 
     fn check_game_state(&mut self) -> GameState {
-        // Check for opened mines
-        if self.mines.intersection(&self.opened).count() > 0 {
-            self.game_state = GameState::Loss;
+        // Opening a mine sets the loss state directly
+        if self.game_state == GameState::Loss {
             return GameState::Loss;
         }
 
+        // Two player games are only decided by the mines found
+        if self.is_two_player() {
+            let majority = self.scores.iter().any(|&score| score * 2 > self.num_mines);
+            if majority || self.num_detonated == self.num_mines {
+                self.game_state = GameState::Win;
+                self.events.push(GameEvent::Won);
+                return GameState::Win;
+            }
+            return GameState::InProgress;
+        }
+
         // Player wins once all fields without a mine have been revealed
-        if self.opened.len() == usize::from(self.width * self.height) - self.mines.len() {
+        if self.num_opened == self.fields.len() - self.num_mines {
             self.game_state = GameState::Win;
+            self.events.push(GameEvent::Won);
             return GameState::Win;
         }
 
@@ -163,15 +574,168 @@ impl Minesweeper {
     }
 
     /// Get the remaining number of mines (according to the player)
-    pub fn remaining_mines(&self) -> usize {
-        if self.mines.len() > self.flagged.len() {
-            return self.mines.len() - self.flagged.len();
-        }
-        return self.flagged.len() - self.mines.len();
+    ///
+    /// Detonated mines are known to the player, so they count as flagged. Negative once more fields are
+    /// flagged than there are mines
+    pub fn remaining_mines(&self) -> isize {
+        let marked = self.num_flagged + self.num_detonated;
+        return self.num_mines as isize - marked as isize;
+    }
+
+    /// Get the id of this game, different for every game created while the program runs
+    ///
+    /// Lets state kept about a board, like the solver's, tell a new game from the one it followed
+    pub fn id(&self) -> u64 {
+        return self.id;
+    }
+
+    /// Get the total number of mines on the board
+    pub fn num_mines(&self) -> usize {
+        return self.num_mines;
+    }
+
+    /// Get the number of mines the player can still hit, including the one that ends the game
+    pub fn lives(&self) -> u8 {
+        return self.lives;
+    }
+
+    /// Check if this game was started with more than one life
+    pub fn has_lives(&self) -> bool {
+        return self.starting_lives > 1 && !self.is_two_player();
     }
 
     pub fn has_started(&self) -> bool {
-        return self.opened.len() > 0;
+        return self.num_opened > 0;
+    }
+
+    // endregion
+
+    // region Board statistics
+
+    /// Get the 3BV of the board, the fewest clicks that clear it without flagging
+    ///
+    /// Every opening counts as one click, along with every safe field not bordering an opening
+    pub fn three_bv(&self) -> usize {
+        let mut counted = vec![false; self.fields.len()];
+        let mut clicks = 0;
+        let positions = (0..self.height).flat_map(|y| (0..self.width).map(move |x| (x, y)));
+
+        // Flood fill each opening, including the numbers around its edge
+        for pos in positions.clone() {
+            let i = self.index(pos).expect("Position is in bounds");
+            if counted[i] || self.is_mined(pos) || self.mine_counts[i] != 0 {
+                continue;
+            }
+
+            clicks += 1;
+            counted[i] = true;
+            let mut stack = vec![pos];
+            while let Some(pos) = stack.pop() {
+                for neighbor in self.neighboring_fields_iter(pos) {
+                    let Some(i) = self.index(neighbor) else {
+                        continue;
+                    };
+                    if counted[i] || self.is_mined(neighbor) {
+                        continue;
+                    }
+                    counted[i] = true;
+                    if self.mine_counts[i] == 0 {
+                        stack.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        // Remaining safe fields each need their own click
+        clicks += positions
+            .filter(|&pos| !self.is_mined(pos) && !counted[self.index(pos).unwrap()])
+            .count();
+        return clicks;
+    }
+
+    // endregion
+
+    // region Two player
+
+    pub fn is_two_player(&self) -> bool {
+        return !self.scores.is_empty();
+    }
+
+    /// Index of the player whose turn it is, always 0 in single player games
+    pub fn current_player(&self) -> usize {
+        return self.current_player;
+    }
+
+    /// Number of mines found by each player, empty in single player games
+    pub fn scores(&self) -> &[usize] {
+        return &self.scores;
+    }
+
+    /// Player who won a finished two player game, or [None] for a draw or while the game is in progress
+    pub fn winner(&self) -> Option<usize> {
+        if self.game_state == GameState::InProgress || !self.is_two_player() {
+            return None;
+        }
+
+        let best = *self.scores.iter().max()?;
+        let mut leaders = (0..self.scores.len()).filter(|&player| self.scores[player] == best);
+        return match (leaders.next(), leaders.next()) {
+            (Some(player), None) => Some(player),
+            _ => None,
+        };
+    }
+
+    /// Pass the turn to the other player
+    fn end_turn(&mut self) {
+        if !self.is_two_player() || self.game_state != GameState::InProgress {
+            return;
+        }
+
+        self.current_player = (self.current_player + 1) % self.scores.len();
+        self.events
+            .push(GameEvent::TurnChanged(self.current_player));
+    }
+
+    // endregion
+
+    // region Events
+
+    /// Take every event emitted since the last call, oldest first
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        return std::mem::take(&mut self.events);
+    }
+
+    // endregion
+
+    // region Move history
+
+    /// Every move accepted so far, oldest first
+    pub fn moves(&self) -> &[Move] {
+        return &self.moves;
+    }
+
+    /// Positions of every mine, in row order
+    ///
+    /// Mines moved away from the first click are already in their final place
+    pub fn mine_positions(&self) -> Vec<Position> {
+        return (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&pos| self.is_mined(pos))
+            .collect();
+    }
+
+    /// Protection given to the first field the player opens
+    pub fn first_click(&self) -> FirstClick {
+        return self.first_click;
+    }
+
+    /// Number of lives the game started with
+    pub fn starting_lives(&self) -> u8 {
+        return self.starting_lives;
+    }
+
+    fn record_move(&mut self, kind: MoveKind, pos: Position) {
+        self.moves.push(Move { kind, pos });
     }
 
     // endregion
@@ -188,14 +752,16 @@ impl Minesweeper {
     }
 
     pub fn neighboring_mines(&self, pos: Position) -> u8 {
-        self.neighboring_fields_iter(pos)
-            .filter(|pos| self.mines.contains(pos))
-            .count() as u8
+        match self.index(pos) {
+            Some(i) => self.mine_counts[i],
+            None => 0,
+        }
     }
 
+    /// Count neighboring fields marked as mines, detonated mines count the same as flags
     pub fn neighboring_flags(&self, pos: Position) -> u8 {
         self.neighboring_fields_iter(pos)
-            .filter(|pos| self.flagged.contains(pos))
+            .filter(|&pos| self.has(pos, FLAG | DETONATED))
             .count() as u8
     }
 
@@ -210,63 +776,284 @@ impl Minesweeper {
 
     // region Player interaction methods
 
+    /// Open the given field, or [None] if it cannot be opened
     pub fn open(&mut self, pos: Position) -> Option<OpenResult> {
-        // Skip the position if the field is flagged
-        if self.is_flagged(pos)
-            || self.is_question(pos)
-            || self.is_open(pos)
-            || self.game_state == GameState::Loss
-        {
-            return None;
+        return self.try_open(pos).ok();
+    }
+
+    /// Open the given field, with an error describing why it could not be opened
+    pub fn try_open(&mut self, pos: Position) -> Result<OpenResult, MinesweeperError> {
+        if self.game_state != GameState::InProgress {
+            return Err(MinesweeperError::GameOver);
         }
-        // Skip the position if the field is out of bounds
         if !self.is_in_bounds(pos) {
-            return None;
+            return Err(MinesweeperError::OutOfBounds(pos));
+        }
+        if self.is_open(pos) {
+            return Err(MinesweeperError::AlreadyOpen(pos));
+        }
+        if self.is_detonated(pos) {
+            return Err(MinesweeperError::Detonated(pos));
+        }
+        if self.is_flagged(pos) || self.is_question(pos) {
+            return Err(MinesweeperError::Marked(pos));
         }
 
-        // Open the field
-        self.opened.insert(pos);
+        self.record_move(MoveKind::Open, pos);
+        if self.num_opened == 0 && self.num_detonated == 0 {
+            self.protect_first_click(pos, self.first_click);
+        }
 
         // Don't open neighboring fields if this one is mined
         if self.is_mined(pos) {
-            self.game_state = GameState::Loss;
-            return Some(OpenResult::Mine);
+            self.detonate(pos);
+            return Ok(OpenResult::Mine);
+        }
+
+        let opened = self.cascade(pos);
+        let result = if opened.len() > 1 {
+            self.events.push(GameEvent::Cascade(opened.clone()));
+            OpenResult::Cascade(opened)
+        } else {
+            let mine_count = self.neighboring_mines(pos);
+            self.events.push(GameEvent::Opened(pos, mine_count));
+            OpenResult::NoMine(mine_count)
+        };
+
+        self.check_game_state();
+        self.end_turn();
+        return Ok(result);
+    }
+
+    /// Move mines away from the first field opened, according to the first click policy
+    ///
+    /// Falls back to less protection if there is no room to move the mines
+    fn protect_first_click(&mut self, pos: Position, first_click: FirstClick) {
+        let protected: Vec<Position> = match first_click {
+            FirstClick::Unprotected => return,
+            FirstClick::Safe => vec![pos],
+            FirstClick::Opening => std::iter::once(pos)
+                .chain(self.neighboring_fields_iter(pos))
+                .collect(),
+        };
+
+        let mined: Vec<Position> = protected
+            .iter()
+            .copied()
+            .filter(|&pos| self.is_mined(pos))
+            .collect();
+        if mined.is_empty() {
+            return;
+        }
+
+        let free_fields: Vec<Position> = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter(|pos| !self.is_mined(*pos) && !protected.contains(pos))
+            .collect();
+        let targets: Vec<Position> = free_fields
+            .choose_multiple(&mut self.rng, mined.len())
+            .copied()
+            .collect();
+
+        if targets.len() < mined.len() {
+            if first_click == FirstClick::Opening {
+                self.protect_first_click(pos, FirstClick::Safe);
+            }
+            return;
         }
 
-        // Open the neighboring fields if safe to do so
-        let mine_count = self.neighboring_mines(pos);
-        let flag_count = self.neighboring_flags(pos);
-        if mine_count == flag_count {
-            for neighbor in self.neighboring_fields_iter(pos) {
-                if !self.is_flagged(neighbor) && !self.is_open(neighbor) {
-                    self.open(neighbor);
+        for (from, to) in mined.into_iter().zip(targets) {
+            self.move_mine(from, to);
+        }
+    }
+
+    /// Handle the player opening a mined field
+    fn detonate(&mut self, pos: Position) {
+        // In two player games the mine is scored instead
+        if self.is_two_player() {
+            self.set(pos, DETONATED);
+            self.num_detonated += 1;
+            self.scores[self.current_player] += 1;
+            self.claims.insert(pos, self.current_player);
+            self.events
+                .push(GameEvent::MineClaimed(pos, self.current_player));
+            self.check_game_state();
+            return;
+        }
+
+        self.lives -= 1;
+        self.events.push(GameEvent::MineDetonated(pos));
+
+        // Mine stays on the board as a marker while the player has lives left
+        if self.lives > 0 {
+            self.set(pos, DETONATED);
+            self.num_detonated += 1;
+            return;
+        }
+
+        self.set(pos, OPEN);
+        self.num_opened += 1;
+        self.game_state = GameState::Loss;
+        self.events.push(GameEvent::Lost);
+    }
+
+    /// Open the given safe field, then keep opening neighbors breadth first while it is safe to do so
+    ///
+    /// Returns every field opened, a mine is only reached if the player flagged the wrong field
+    fn cascade(&mut self, pos: Position) -> Vec<Position> {
+        let mut opened = Vec::new();
+        let mut queue = VecDeque::from([pos]);
+        self.set(pos, OPEN);
+        self.num_opened += 1;
+
+        while let Some(current) = queue.pop_front() {
+            opened.push(current);
+
+            // Only spread once every neighboring mine is accounted for
+            if self.neighboring_mines(current) != self.neighboring_flags(current) {
+                continue;
+            }
+
+            for neighbor in self.neighboring_fields_iter(current) {
+                if self.has(neighbor, OPEN | FLAG | QUESTION | DETONATED) {
+                    continue;
+                }
+
+                if self.is_mined(neighbor) {
+                    opened.push(neighbor);
+                    self.detonate(neighbor);
+                    if self.game_state == GameState::Loss {
+                        return opened;
+                    }
+                    continue;
                 }
+
+                self.set(neighbor, OPEN);
+                self.num_opened += 1;
+                queue.push_back(neighbor);
             }
         }
 
+        return opened;
+    }
+
+    /// Open every unmarked neighbor of an open field once it has as many flags around it as mines
+    ///
+    /// Returns the fields opened, which is empty if the flags do not match the mine count
+    pub fn try_chord(&mut self, pos: Position) -> Result<Vec<Position>, MinesweeperError> {
+        if self.game_state != GameState::InProgress {
+            return Err(MinesweeperError::GameOver);
+        }
+        if !self.is_in_bounds(pos) {
+            return Err(MinesweeperError::OutOfBounds(pos));
+        }
+        if !self.is_open(pos) {
+            return Err(MinesweeperError::NotOpen(pos));
+        }
+        if self.neighboring_mines(pos) != self.neighboring_flags(pos) {
+            return Ok(Vec::new());
+        }
+        self.record_move(MoveKind::Chord, pos);
+
+        let mut opened = Vec::new();
+        for neighbor in self.neighboring_fields_iter(pos) {
+            if self.game_state != GameState::InProgress {
+                break;
+            }
+            if self.has(neighbor, OPEN | FLAG | QUESTION | DETONATED) {
+                continue;
+            }
+
+            // Wrongly placed flags let the chord hit a mine
+            if self.is_mined(neighbor) {
+                opened.push(neighbor);
+                self.detonate(neighbor);
+                continue;
+            }
+            opened.append(&mut self.cascade(neighbor));
+        }
+
+        if !opened.is_empty() {
+            self.events.push(GameEvent::Cascade(opened.clone()));
+        }
         self.check_game_state();
-        return Some(OpenResult::NoMine(mine_count));
+        if !opened.is_empty() && opened.iter().all(|&pos| !self.is_mined(pos)) {
+            self.end_turn();
+        }
+        return Ok(opened);
     }
 
+    /// Cycle the mark on the given field, ignoring fields that cannot be marked
     pub fn flag(&mut self, pos: Position) {
-        // Skip the position if the field is opened
-        if self.is_open(pos) || self.game_state == GameState::Loss {
-            return;
+        self.try_flag(pos).ok();
+    }
+
+    /// Cycle the mark on the given field, with an error describing why it could not be marked
+    ///
+    /// With question marks the cycle is unknown → flag → question → unknown, without them it is unknown → flag → unknown
+    pub fn try_flag(&mut self, pos: Position) -> Result<(), MinesweeperError> {
+        if self.game_state != GameState::InProgress {
+            return Err(MinesweeperError::GameOver);
         }
-        // Skip the position if the field is out of bounds
         if !self.is_in_bounds(pos) {
-            return;
+            return Err(MinesweeperError::OutOfBounds(pos));
+        }
+        if self.is_open(pos) {
+            return Err(MinesweeperError::AlreadyOpen(pos));
+        }
+        if self.is_detonated(pos) {
+            return Err(MinesweeperError::Detonated(pos));
         }
 
+        self.record_move(MoveKind::Flag, pos);
         if self.is_flagged(pos) {
-            self.flagged.remove(&pos);
-            self.question.insert(pos);
+            self.unset(pos, FLAG);
+            self.num_flagged -= 1;
+            self.events.push(GameEvent::FlagRemoved(pos));
+
+            if self.question_marks {
+                self.set(pos, QUESTION);
+                self.events.push(GameEvent::QuestionPlaced(pos));
+            }
         } else if self.is_question(pos) {
-            self.question.remove(&pos);
+            self.unset(pos, QUESTION);
+            self.events.push(GameEvent::QuestionRemoved(pos));
         } else {
-            self.flagged.insert(pos);
-            self.check_game_state();
+            self.set(pos, FLAG);
+            self.num_flagged += 1;
+            self.events.push(GameEvent::FlagPlaced(pos));
+        }
+
+        return Ok(());
+    }
+
+    /// Remove the flag or question mark on the given field, ignoring fields that are not marked
+    pub fn clear_mark(&mut self, pos: Position) {
+        self.try_clear_mark(pos).ok();
+    }
+
+    /// Remove the flag or question mark on the given field, with an error describing why it could not be cleared
+    pub fn try_clear_mark(&mut self, pos: Position) -> Result<(), MinesweeperError> {
+        if self.game_state != GameState::InProgress {
+            return Err(MinesweeperError::GameOver);
+        }
+        if !self.is_in_bounds(pos) {
+            return Err(MinesweeperError::OutOfBounds(pos));
+        }
+
+        if self.is_flagged(pos) {
+            self.unset(pos, FLAG);
+            self.num_flagged -= 1;
+            self.events.push(GameEvent::FlagRemoved(pos));
+        } else if self.is_question(pos) {
+            self.unset(pos, QUESTION);
+            self.events.push(GameEvent::QuestionRemoved(pos));
+        } else {
+            return Err(MinesweeperError::NotMarked(pos));
         }
+
+        self.record_move(MoveKind::Unflag, pos);
+        return Ok(());
     }
 
     // endregion
@@ -275,24 +1062,28 @@ impl Minesweeper {
 impl Display for Minesweeper {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Indicate the number of mines remaining
-        let remaining_mines = self.mines.len() - self.flagged.len();
-        write!(f, "Remaining Mines: {}\n", remaining_mines)?;
+        writeln!(f, "Remaining Mines: {}", self.remaining_mines())?;
+        if self.has_lives() {
+            writeln!(f, "Lives: {}", self.lives)?;
+        }
 
         // Print board
-        for y in (0..self.width).rev() {
+        for y in (0..self.height).rev() {
             // Print row heading
             write!(f, "|{}|", y)?;
 
             // Print each column for the given row
-            for x in 0..self.height {
+            for x in 0..self.width {
                 let pos = (x, y);
 
                 // Display the mine in the current space if the game is lost or the mine is revealed
-                if self.is_mined(pos) {
-                    if self.is_open(pos) || self.game_state == GameState::Loss {
-                        f.write_str("💣 ")?;
-                        continue;
-                    }
+                if self.is_mined(pos)
+                    && (self.is_open(pos)
+                        || self.is_detonated(pos)
+                        || self.game_state == GameState::Loss)
+                {
+                    f.write_str("💣 ")?;
+                    continue;
                 }
 
                 // If the tile is open, display the number of neighboring mines
@@ -323,7 +1114,7 @@ impl Display for Minesweeper {
         for x in 0..self.width {
             write!(f, "|{}|", x)?;
         }
-        write!(f, "\n")?;
+        writeln!(f)?;
 
         // Inform the user if they have lost
         if self.game_state == GameState::Loss {
@@ -348,7 +1139,7 @@ mod tests {
 
         // Check the number of mines is correct
         println!("{:?}", ms);
-        assert!(ms.mines.len() == num_mines);
+        assert!(ms.num_mines() == num_mines);
     }
 
     #[test]
@@ -361,9 +1152,424 @@ mod tests {
         println!("{}", ms);
     }
 
+    #[test]
+    fn lives_test() {
+        let mut ms = Minesweeper::from_mines(10, 10, &[(0, 0), (9, 9)]).with_lives(2);
+
+        // First mine costs a life and stays on the board as a marker
+        ms.open((0, 0));
+        assert_eq!(ms.game_state, GameState::InProgress);
+        assert_eq!(ms.lives(), 1);
+        assert!(ms.is_detonated((0, 0)));
+        assert_eq!(ms.neighboring_flags((1, 1)), 1);
+        assert_eq!(ms.remaining_mines(), 1);
+
+        // Second mine ends the game
+        ms.open((9, 9));
+        assert_eq!(ms.game_state, GameState::Loss);
+        assert_eq!(ms.lives(), 0);
+    }
+
+    #[test]
+    fn lives_win_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]).with_lives(3);
+
+        // Detonated mine does not block the win
+        ms.open((0, 0));
+        ms.open((2, 2));
+        assert_eq!(ms.game_state, GameState::Win);
+    }
+
+    #[test]
+    fn neighbor_count_test() {
+        let ms = Minesweeper::from_mines(4, 3, &[(0, 0), (1, 0), (3, 2)]);
+        assert_eq!(ms.num_mines(), 3);
+        assert_eq!(ms.neighboring_mines((0, 1)), 2);
+        assert_eq!(ms.neighboring_mines((2, 1)), 2);
+        assert_eq!(ms.neighboring_mines((3, 0)), 0);
+        assert_eq!(ms.neighboring_mines((9, 9)), 0);
+    }
+
+    #[test]
+    fn cascade_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(2, 2)]);
+
+        // Opening a numbered field does not spread
+        let result = ms.open((1, 1));
+        assert!(matches!(result, Some(OpenResult::NoMine(1))));
+
+        // Opening a zero lists every field it opened, starting with the one clicked
+        let result = ms.open((0, 0));
+        let Some(OpenResult::Cascade(opened)) = result else {
+            panic!("Expected a cascade");
+        };
+        assert_eq!(opened.first(), Some(&(0, 0)));
+        assert_eq!(opened.len(), 7);
+        assert_eq!(ms.game_state, GameState::Win);
+    }
+
+    #[test]
+    fn large_cascade_test() {
+        // A single opening that would overflow the stack if done recursively
+        let mut ms = Minesweeper::from_mines(500, 500, &[(499, 499)]);
+        let result = ms.open((0, 0));
+        let Some(OpenResult::Cascade(opened)) = result else {
+            panic!("Expected a cascade");
+        };
+        assert_eq!(opened.len(), 500 * 500 - 1);
+        assert_eq!(ms.game_state, GameState::Win);
+    }
+
+    #[test]
+    fn invalid_board_test() {
+        assert_eq!(
+            Minesweeper::try_new(3, 3, 9).unwrap_err(),
+            MinesweeperError::TooManyMines {
+                mines: 9,
+                fields: 9
+            }
+        );
+        assert_eq!(
+            Minesweeper::try_new(0, 3, 0).unwrap_err(),
+            MinesweeperError::EmptyBoard
+        );
+        assert_eq!(Minesweeper::try_new(3, 3, 8).unwrap().num_mines(), 8);
+    }
+
+    #[test]
+    fn invalid_move_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0), (2, 2)]).with_lives(2);
+
+        assert_eq!(
+            ms.try_open((3, 0)).unwrap_err(),
+            MinesweeperError::OutOfBounds((3, 0))
+        );
+        assert_eq!(
+            ms.try_flag((0, 3)).unwrap_err(),
+            MinesweeperError::OutOfBounds((0, 3))
+        );
+
+        ms.try_open((1, 1)).unwrap();
+        assert_eq!(
+            ms.try_open((1, 1)).unwrap_err(),
+            MinesweeperError::AlreadyOpen((1, 1))
+        );
+        assert_eq!(
+            ms.try_flag((1, 1)).unwrap_err(),
+            MinesweeperError::AlreadyOpen((1, 1))
+        );
+
+        ms.try_flag((0, 1)).unwrap();
+        assert_eq!(
+            ms.try_open((0, 1)).unwrap_err(),
+            MinesweeperError::Marked((0, 1))
+        );
+
+        ms.try_open((0, 0)).unwrap();
+        assert_eq!(
+            ms.try_open((0, 0)).unwrap_err(),
+            MinesweeperError::Detonated((0, 0))
+        );
+
+        ms.try_open((2, 2)).unwrap();
+        assert_eq!(ms.try_open((1, 0)).unwrap_err(), MinesweeperError::GameOver);
+        assert_eq!(ms.try_flag((1, 0)).unwrap_err(), MinesweeperError::GameOver);
+    }
+
+    #[test]
+    fn events_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0), (2, 2)]).with_lives(2);
+
+        ms.flag((0, 1));
+        ms.flag((0, 1));
+        ms.flag((0, 1));
+        assert_eq!(
+            ms.drain_events(),
+            vec![
+                GameEvent::FlagPlaced((0, 1)),
+                GameEvent::FlagRemoved((0, 1)),
+                GameEvent::QuestionPlaced((0, 1)),
+                GameEvent::QuestionRemoved((0, 1)),
+            ]
+        );
+
+        ms.open((1, 1));
+        ms.open((0, 0));
+        assert_eq!(
+            ms.drain_events(),
+            vec![
+                GameEvent::Opened((1, 1), 2),
+                GameEvent::MineDetonated((0, 0))
+            ]
+        );
+
+        ms.open((2, 2));
+        assert_eq!(
+            ms.drain_events(),
+            vec![GameEvent::MineDetonated((2, 2)), GameEvent::Lost]
+        );
+        assert!(ms.drain_events().is_empty());
+    }
+
+    #[test]
+    fn question_mark_cycle_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]);
+        assert!(ms.has_question_marks());
+
+        // Unknown -> flag -> question -> unknown
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+        ms.flag((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Flagged);
+        assert_eq!(ms.remaining_mines(), 0);
+        ms.flag((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Question);
+        assert_eq!(ms.remaining_mines(), 1);
+        ms.flag((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+
+        // Question marked fields cannot be opened
+        ms.flag((1, 0));
+        ms.flag((1, 0));
+        assert_eq!(ms.try_open((1, 0)), Err(MinesweeperError::Marked((1, 0))));
+    }
+
+    #[test]
+    fn clear_mark_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]);
+
+        // Flags and question marks both go straight back to unknown
+        ms.flag((1, 0));
+        ms.clear_mark((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+        assert_eq!(ms.remaining_mines(), 1);
+        ms.flag((1, 0));
+        ms.flag((1, 0));
+        ms.clear_mark((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+        assert_eq!(
+            ms.drain_events(),
+            vec![
+                GameEvent::FlagPlaced((1, 0)),
+                GameEvent::FlagRemoved((1, 0)),
+                GameEvent::FlagPlaced((1, 0)),
+                GameEvent::FlagRemoved((1, 0)),
+                GameEvent::QuestionPlaced((1, 0)),
+                GameEvent::QuestionRemoved((1, 0)),
+            ]
+        );
+
+        assert_eq!(
+            ms.try_clear_mark((1, 0)),
+            Err(MinesweeperError::NotMarked((1, 0)))
+        );
+        assert_eq!(
+            ms.try_clear_mark((3, 0)),
+            Err(MinesweeperError::OutOfBounds((3, 0)))
+        );
+    }
+
+    #[test]
+    fn flag_toggle_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]).with_question_marks(false);
+        assert!(!ms.has_question_marks());
+
+        // Unknown -> flag -> unknown
+        ms.flag((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Flagged);
+        ms.flag((1, 0));
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+        assert!(!ms.is_question((1, 0)));
+        assert_eq!(
+            ms.drain_events(),
+            vec![
+                GameEvent::FlagPlaced((1, 0)),
+                GameEvent::FlagRemoved((1, 0)),
+            ]
+        );
+
+        // Flagging more fields than there are mines takes the count below zero
+        ms.flag((1, 0));
+        ms.flag((2, 0));
+        assert_eq!(ms.remaining_mines(), -1);
+        assert!(ms.to_string().starts_with("Remaining Mines: -1\n"));
+        ms.flag((2, 0));
+        ms.flag((1, 0));
+        assert_eq!(ms.remaining_mines(), 1);
+
+        // Marks are still refused on open fields
+        ms.open((1, 1));
+        assert_eq!(
+            ms.try_flag((1, 1)),
+            Err(MinesweeperError::AlreadyOpen((1, 1)))
+        );
+    }
+
+    #[test]
+    fn disable_question_marks_test() {
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]);
+        ms.flag((1, 0));
+        ms.flag((1, 0));
+        ms.flag((2, 2));
+        ms.drain_events();
+
+        // Existing question marks are cleared, flags are kept
+        ms.set_question_marks(false);
+        assert_eq!(ms.get_field_state((1, 0)), FieldState::Unknown);
+        assert_eq!(ms.get_field_state((2, 2)), FieldState::Flagged);
+        assert_eq!(ms.drain_events(), vec![GameEvent::QuestionRemoved((1, 0))]);
+        assert!(ms.open((1, 0)).is_some());
+
+        // Re-enabling resumes the full cycle
+        ms.set_question_marks(true);
+        ms.flag((2, 2));
+        assert_eq!(ms.get_field_state((2, 2)), FieldState::Question);
+    }
+
+    #[test]
+    fn first_click_test() {
+        // Mine is moved away from the first field opened, the seed decides where to
+        let mut ms = Minesweeper::new_seeded(3, 3, 1, 2).with_first_click(FirstClick::Safe);
+        assert_eq!(ms.mine_positions(), vec![(0, 0)]);
+        ms.open((0, 0));
+        assert!(ms.is_open((0, 0)));
+        assert_eq!(ms.mine_positions(), vec![(0, 1)]);
+        assert_eq!(ms.game_state, GameState::InProgress);
+
+        // Neighbors are cleared too, so the first open cascades
+        let mut ms =
+            Minesweeper::from_mines(5, 5, &[(1, 1), (2, 2)]).with_first_click(FirstClick::Opening);
+        ms.open((1, 2));
+        assert_eq!(ms.neighboring_mines((1, 2)), 0);
+        assert!(ms.is_open((0, 1)));
+        assert_eq!(ms.num_mines(), 2);
+
+        // Not enough room for an opening, but the first field is still safe
+        let mines = [(0, 0), (1, 0), (2, 0), (0, 1), (2, 1), (0, 2)];
+        let mut ms = Minesweeper::from_mines(3, 3, &mines).with_first_click(FirstClick::Opening);
+        ms.open((0, 0));
+        assert!(ms.is_open((0, 0)));
+        assert!(ms.is_mined((1, 0)));
+        assert_eq!(ms.num_mines(), 6);
+    }
+
+    #[test]
+    fn chord_test() {
+        let mut ms = Minesweeper::from_mines(4, 3, &[(0, 0), (3, 2)]);
+        assert_eq!(ms.try_chord((1, 1)), Err(MinesweeperError::NotOpen((1, 1))));
+
+        // Nothing happens until the flags match the mine count
+        ms.open((1, 1));
+        assert_eq!(ms.try_chord((1, 1)), Ok(Vec::new()));
+        ms.flag((0, 0));
+        ms.drain_events();
+        let opened = ms.try_chord((1, 1)).unwrap();
+        assert_eq!(opened.len(), 9);
+        assert!(ms.is_open((3, 0)));
+        assert!(!ms.is_open((3, 2)));
+        assert_eq!(
+            ms.drain_events(),
+            vec![GameEvent::Cascade(opened), GameEvent::Won]
+        );
+
+        // A wrong flag makes the chord hit the mine it was hiding
+        let mut ms = Minesweeper::from_mines(3, 3, &[(0, 0)]);
+        ms.open((1, 1));
+        ms.flag((2, 2));
+        assert_eq!(ms.try_chord((1, 1)), Ok(vec![(0, 0)]));
+        assert_eq!(ms.game_state, GameState::Loss);
+    }
+
+    #[test]
+    fn two_player_test() {
+        let mines = [(0, 0), (4, 0), (0, 4)];
+        let mut ms = Minesweeper::from_mines(5, 5, &mines).with_two_players();
+        assert!(ms.is_two_player());
+        assert!(!ms.has_lives());
+        assert_eq!(ms.current_player(), 0);
+
+        // Opening a safe field passes the turn
+        ms.open((1, 1));
+        assert_eq!(ms.current_player(), 1);
+
+        // Finding a mine scores and keeps the turn
+        ms.open((0, 0));
+        assert_eq!(ms.scores(), &[0, 1]);
+        assert_eq!(ms.current_player(), 1);
+        assert_eq!(ms.get_field_state((0, 0)), FieldState::Claimed(1));
+        assert!(ms.try_open((0, 0)).is_err());
+        assert_eq!(ms.game_state, GameState::InProgress);
+        assert_eq!(
+            ms.drain_events(),
+            vec![
+                GameEvent::Opened((1, 1), 1),
+                GameEvent::TurnChanged(1),
+                GameEvent::MineClaimed((0, 0), 1),
+            ]
+        );
+
+        // More than half of the mines wins
+        ms.open((4, 0));
+        assert_eq!(ms.game_state, GameState::Win);
+        assert_eq!(ms.winner(), Some(1));
+        assert_eq!(ms.get_field_state((4, 0)), FieldState::Claimed(1));
+    }
+
+    #[test]
+    fn two_player_draw_test() {
+        let mut ms = Minesweeper::from_mines(4, 1, &[(0, 0), (3, 0)]).with_two_players();
+        ms.open((1, 0));
+        ms.open((0, 0));
+        ms.open((2, 0));
+
+        // Clearing every safe field does not end the game
+        assert_eq!(ms.game_state, GameState::InProgress);
+        assert_eq!(ms.current_player(), 0);
+
+        // Neither player has more than half once the last mine is found, so it is a draw
+        ms.open((3, 0));
+        assert_eq!(ms.scores(), &[1, 1]);
+        assert_eq!(ms.game_state, GameState::Win);
+        assert_eq!(ms.winner(), None);
+    }
+
+    #[test]
+    fn seeded_test() {
+        let a = Minesweeper::new_seeded(30, 16, 99, 42).with_first_click(FirstClick::Opening);
+        let b = Minesweeper::new_seeded(30, 16, 99, 42).with_first_click(FirstClick::Opening);
+        let c = Minesweeper::new_seeded(30, 16, 99, 43);
+        let mines = |game: &Minesweeper| {
+            (0..game.height)
+                .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+                .filter(|pos| game.is_mined(*pos))
+                .collect::<Vec<Position>>()
+        };
+        assert_eq!(mines(&a), mines(&b));
+        assert_ne!(mines(&a), mines(&c));
+
+        // Mines moved by first click protection are placed the same way
+        let (mut a, mut b) = (a, b);
+        a.open((0, 0));
+        b.open((0, 0));
+        assert_eq!(mines(&a), mines(&b));
+    }
+
+    #[test]
+    fn three_bv_test() {
+        // Openings on either side of the mines, the field between them is a separate click
+        let game = Minesweeper::from_mines(5, 3, &[(2, 0), (2, 2)]);
+        assert_eq!(game.three_bv(), 2 + 1);
+
+        // No openings, every safe field is a click
+        let game = Minesweeper::from_mines(3, 1, &[(1, 0)]);
+        assert_eq!(game.three_bv(), 2);
+
+        let game = Minesweeper::from_mines(3, 3, &[]);
+        assert_eq!(game.three_bv(), 1);
+    }
+
     #[test]
     fn bounds_test() {
         let ms = Minesweeper::new(10, 10, 10);
-        assert!(ms.is_in_bounds((5, 5)) == true);
+        assert!(ms.is_in_bounds((5, 5)));
     }
 }
@@ -0,0 +1,167 @@
+//! Property tests of the game rules and the solver on random boards and random moves
+//!
+//! Every generated game is checked after each move, so a failure shrinks down to the smallest board
+//! and the shortest list of moves that break the rule.
+
+use proptest::prelude::*;
+
+use crate::{
+    minesweeper::*,
+    solver::{Solver, SolverStep, SolverStrategy},
+};
+
+/// Rules for a random game: size, mine count, seed, lives and first click protection
+fn games() -> impl Strategy<Value = Minesweeper> {
+    (1..=12u16, 1..=12u16)
+        .prop_flat_map(|(width, height)| {
+            let area = usize::from(width) * usize::from(height);
+            (
+                Just((width, height)),
+                0..area,
+                any::<u64>(),
+                1..=3u8,
+                prop_oneof![
+                    Just(FirstClick::Unprotected),
+                    Just(FirstClick::Safe),
+                    Just(FirstClick::Opening)
+                ],
+                any::<bool>(),
+            )
+        })
+        .prop_map(
+            |((width, height), mines, seed, lives, first_click, question_marks)| {
+                Minesweeper::new_seeded(width, height, mines, seed)
+                    .with_lives(lives)
+                    .with_first_click(first_click)
+                    .with_question_marks(question_marks)
+            },
+        )
+}
+
+/// Moves anywhere on the largest board, wrapped onto the board they are played on
+fn moves() -> impl Strategy<Value = Vec<Move>> {
+    let kind = prop_oneof![
+        3 => Just(MoveKind::Open),
+        2 => Just(MoveKind::Flag),
+        1 => Just(MoveKind::Unflag),
+        1 => Just(MoveKind::Chord),
+    ];
+    return prop::collection::vec(
+        (kind, 0..12u16, 0..12u16).prop_map(|(kind, x, y)| Move { kind, pos: (x, y) }),
+        0..40,
+    );
+}
+
+fn positions(game: &Minesweeper) -> Vec<Position> {
+    return (0..game.height)
+        .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+        .collect();
+}
+
+fn count(game: &Minesweeper, check: impl Fn(Position) -> bool) -> usize {
+    return positions(game)
+        .into_iter()
+        .filter(|&pos| check(pos))
+        .count();
+}
+
+/// Mines that were opened or detonated, as opposed to ones still hidden
+fn hit_mines(game: &Minesweeper) -> Vec<Position> {
+    return positions(game)
+        .into_iter()
+        .filter(|&pos| game.is_mined(pos) && (game.is_open(pos) || game.is_detonated(pos)))
+        .collect();
+}
+
+/// Check the rules that hold after any move
+fn check_invariants(game: &Minesweeper, mines: usize) -> Result<(), TestCaseError> {
+    prop_assert_eq!(game.num_mines(), mines);
+    prop_assert_eq!(count(game, |pos| game.is_mined(pos)), mines);
+
+    for pos in positions(game) {
+        let around = game
+            .neighboring_fields_iter(pos)
+            .filter(|&neighbor| game.is_mined(neighbor))
+            .count();
+        prop_assert!(game.neighboring_mines(pos) <= 8);
+        prop_assert_eq!(usize::from(game.neighboring_mines(pos)), around);
+    }
+
+    let safe_open = count(game, |pos| game.is_open(pos) && !game.is_mined(pos));
+    let area = usize::from(game.width) * usize::from(game.height);
+    prop_assert_eq!(game.game_state == GameState::Win, safe_open == area - mines);
+
+    let marked =
+        count(game, |pos| game.is_flagged(pos)) + count(game, |pos| game.is_detonated(pos));
+    prop_assert_eq!(game.remaining_mines(), mines as isize - marked as isize);
+    return Ok(());
+}
+
+proptest! {
+    #[test]
+    fn game_invariants(mut game in games(), moves in moves()) {
+        let mines = game.num_mines();
+        check_invariants(&game, mines)?;
+
+        for player_move in moves {
+            let pos = (player_move.pos.0 % game.width, player_move.pos.1 % game.height);
+            let wrong_flag = positions(&game)
+                .into_iter()
+                .any(|pos| game.is_flagged(pos) && !game.is_mined(pos));
+            let hit_before = hit_mines(&game);
+
+            let _ = match player_move.kind {
+                MoveKind::Open => game.try_open(pos).map(|_| ()),
+                MoveKind::Flag => game.try_flag(pos),
+                MoveKind::Unflag => game.try_clear_mark(pos),
+                MoveKind::Chord => game.try_chord(pos).map(|_| ()),
+            };
+            check_invariants(&game, mines)?;
+
+            // Cascades only reach a mine through a wrong flag, otherwise only the opened field can be one
+            if player_move.kind == MoveKind::Open && !wrong_flag {
+                let hit: Vec<Position> = hit_mines(&game)
+                    .into_iter()
+                    .filter(|hit| !hit_before.contains(hit))
+                    .collect();
+                prop_assert!(hit.iter().all(|&hit| hit == pos), "opened {:?}, hit {:?}", pos, hit);
+            }
+        }
+    }
+
+    #[test]
+    fn solver_certainty(mut game in games(), opens in prop::collection::vec((0..12u16, 0..12u16), 1..4)) {
+        for (x, y) in opens {
+            game.open((x % game.width, y % game.height));
+        }
+
+        // The safe only solver makes certain moves only, so every one of them must be right, apart from
+        // the opening move which relies on first click protection
+        let mut solver = Solver::new().with_strategy(SolverStrategy::SafeOnly);
+        while game.game_state == GameState::InProgress {
+            solver.calculate_field(&game);
+            for pos in positions(&game) {
+                if game.is_open(pos) || game.is_detonated(pos) {
+                    continue;
+                }
+                let chance = solver.get_mine_chance(pos).value();
+                prop_assert!(chance < 1.0 || game.is_mined(pos), "{:?} is not a mine", pos);
+                prop_assert!(chance > 0.0 || !game.is_mined(pos), "{:?} is a mine", pos);
+            }
+
+            match solver.solve_step(&game) {
+                SolverStep::Open(pos) => {
+                    prop_assert!(!game.is_open(pos) && !game.is_detonated(pos), "opened {:?} again", pos);
+                    prop_assert!(!game.has_started() || !game.is_mined(pos), "opened the mine at {:?}", pos);
+                    game.open(pos);
+                }
+                SolverStep::Flag(pos) => {
+                    prop_assert!(game.is_mined(pos), "flagged {:?} without a mine", pos);
+                    game.flag(pos);
+                }
+                SolverStep::Unflag(pos) => game.clear_mark(pos),
+                SolverStep::None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Line based text protocol for playing games with external bots
+//!
+//! The host drives the bot, sending lines to its input and reading one reply per request:
+//!
+//! ```text
+//! host: minesweeper 1               bot: ready
+//! host: newgame <width> <height> <mines> <lives>
+//! host: board
+//! host: <one line per row, see below>
+//! host: go                          bot: open <x> <y> | flag <x> <y> | unflag <x> <y> | resign
+//! host: error <reason>              (only after an invalid move, followed by the board again)
+//! host: result win|loss|unfinished
+//! host: quit
+//! ```
+//!
+//! Board rows are sent top to bottom with one character per field: `#` closed, `F` flagged,
+//! `?` question mark, `*` detonated or found mine, or `0`-`8` for an open field and its mine count.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use crate::{minesweeper::*, solver::SolverStep};
+
+/// Protocol version sent in the handshake
+pub const VERSION: u32 = 1;
+
+/// Errors that can occur while talking to a bot
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    /// The bot closed its output
+    Disconnected,
+    /// The bot sent a line that is not valid at this point of the protocol
+    Unexpected(String),
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Io(error) => write!(f, "I/O error: {}", error),
+            ProtocolError::Disconnected => f.write_str("Bot disconnected"),
+            ProtocolError::Unexpected(line) => write!(f, "Unexpected reply '{}'", line),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl From<io::Error> for ProtocolError {
+    fn from(error: io::Error) -> Self {
+        ProtocolError::Io(error)
+    }
+}
+
+/// Outcome of a single game played by a bot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameReport {
+    /// Final state, still in progress if the bot resigned or ran out of moves
+    pub state: GameState,
+    /// Number of moves made, including invalid ones
+    pub moves: usize,
+    /// Number of moves rejected by the game
+    pub invalid_moves: usize,
+}
+
+/// Get the character sent for a field, only showing what the player can see
+pub fn field_char(state: FieldState) -> char {
+    match state {
+        FieldState::Unknown => '#',
+        FieldState::Flagged => 'F',
+        FieldState::Question => '?',
+        FieldState::MineDetonated | FieldState::Claimed(_) => '*',
+        FieldState::Open(count) => char::from(b'0' + count),
+        // Only shown once the game is over
+        FieldState::MineRevealed => 'M',
+        FieldState::NoMine => 'X',
+    }
+}
+
+/// Get the rows of the board as sent to a bot
+pub fn format_board(game: &Minesweeper) -> Vec<String> {
+    return (0..game.height)
+        .map(|y| {
+            (0..game.width)
+                .map(|x| field_char(game.get_field_state((x, y))))
+                .collect()
+        })
+        .collect();
+}
+
+/// Parse a move sent by a bot, resigning is returned as [SolverStep::None]
+pub fn parse_move(line: &str) -> Option<SolverStep> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next()?;
+    if command == "resign" {
+        return parts.next().is_none().then_some(SolverStep::None);
+    }
+
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    match command {
+        "open" => Some(SolverStep::Open((x, y))),
+        "flag" => Some(SolverStep::Flag((x, y))),
+        "unflag" => Some(SolverStep::Unflag((x, y))),
+        _ => None,
+    }
+}
+
+/// Format a move the way a bot sends it
+pub fn format_move(step: SolverStep) -> String {
+    match step {
+        SolverStep::Open((x, y)) => format!("open {} {}", x, y),
+        SolverStep::Flag((x, y)) => format!("flag {} {}", x, y),
+        SolverStep::Unflag((x, y)) => format!("unflag {} {}", x, y),
+        SolverStep::None => String::from("resign"),
+    }
+}
+
+/// Apply a move to the game, returning why it was rejected if it is invalid
+pub fn apply_move(game: &mut Minesweeper, step: SolverStep) -> Result<(), MinesweeperError> {
+    match step {
+        SolverStep::Open(pos) => game.try_open(pos).map(|_| ()),
+        SolverStep::Flag(pos) => game.try_flag(pos),
+        SolverStep::Unflag(pos) => game.try_clear_mark(pos),
+        SolverStep::None => Ok(()),
+    }
+}
+
+/// Host side of the protocol, plays games against a bot connected to the given reader and writer
+pub struct Host<R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+}
+
+impl<R: BufRead, W: Write> Host<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    /// Check the bot speaks the protocol
+    pub fn handshake(&mut self) -> Result<(), ProtocolError> {
+        self.send(&format!("minesweeper {}", VERSION))?;
+        let reply = self.receive()?;
+        if reply != "ready" {
+            return Err(ProtocolError::Unexpected(reply));
+        }
+        return Ok(());
+    }
+
+    /// Play a game to the end, stopping early if the bot resigns or makes too many moves
+    pub fn play(
+        &mut self,
+        game: &mut Minesweeper,
+        max_moves: usize,
+    ) -> Result<GameReport, ProtocolError> {
+        self.send(&format!(
+            "newgame {} {} {} {}",
+            game.width,
+            game.height,
+            game.num_mines(),
+            game.lives()
+        ))?;
+
+        let mut report = GameReport {
+            state: game.game_state,
+            moves: 0,
+            invalid_moves: 0,
+        };
+        while game.game_state == GameState::InProgress && report.moves < max_moves {
+            self.send("board")?;
+            for row in format_board(game) {
+                self.send(&row)?;
+            }
+            self.send("go")?;
+
+            let reply = self.receive()?;
+            let step = parse_move(&reply).ok_or(ProtocolError::Unexpected(reply))?;
+            if step == SolverStep::None {
+                break;
+            }
+
+            report.moves += 1;
+            if let Err(error) = apply_move(game, step) {
+                log::debug!("Bot made invalid move '{}': {}", format_move(step), error);
+                report.invalid_moves += 1;
+                self.send(&format!("error {}", error))?;
+            }
+        }
+        game.drain_events();
+
+        report.state = game.game_state;
+        self.send(match report.state {
+            GameState::Win => "result win",
+            GameState::Loss => "result loss",
+            GameState::InProgress => "result unfinished",
+        })?;
+        return Ok(report);
+    }
+
+    /// Tell the bot to exit
+    pub fn quit(&mut self) -> Result<(), ProtocolError> {
+        return self.send("quit");
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), ProtocolError> {
+        log::trace!("> {}", line);
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        return Ok(());
+    }
+
+    fn receive(&mut self) -> Result<String, ProtocolError> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(ProtocolError::Disconnected);
+        }
+        log::trace!("< {}", line.trim_end());
+        return Ok(line.trim().to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_move_test() {
+        assert_eq!(parse_move("open 3 4"), Some(SolverStep::Open((3, 4))));
+        assert_eq!(parse_move(" flag 0 12 "), Some(SolverStep::Flag((0, 12))));
+        assert_eq!(parse_move("resign"), Some(SolverStep::None));
+        assert_eq!(parse_move("open 3"), None);
+        assert_eq!(parse_move("open -1 2"), None);
+        assert_eq!(parse_move("open 1 2 3"), None);
+        assert_eq!(parse_move("dig 1 2"), None);
+        assert_eq!(parse_move(""), None);
+
+        assert_eq!(parse_move("unflag 2 1"), Some(SolverStep::Unflag((2, 1))));
+
+        for step in [
+            SolverStep::Open((5, 6)),
+            SolverStep::Flag((7, 8)),
+            SolverStep::Unflag((9, 10)),
+        ] {
+            assert_eq!(parse_move(&format_move(step)), Some(step));
+        }
+    }
+
+    #[test]
+    fn format_board_test() {
+        let mut game = Minesweeper::from_mines(4, 2, &[(3, 0)]).with_lives(2);
+        game.open((0, 0));
+        game.flag((3, 1));
+        game.open((3, 0));
+        assert_eq!(format_board(&game), vec!["001*", "001F"]);
+    }
+
+    #[test]
+    fn host_test() {
+        // Bot replies, including a move out of bounds and a move on an open field
+        let replies = "ready\nopen 9 9\nopen 1 0\nopen 1 0\nflag 2 0\nopen 0 0\n";
+        let mut output = Vec::new();
+        let mut host = Host::new(replies.as_bytes(), &mut output);
+        host.handshake().unwrap();
+
+        let mut game = Minesweeper::from_mines(3, 3, &[(2, 0)]);
+        let report = host.play(&mut game, 100).unwrap();
+        assert_eq!(report.state, GameState::Win);
+        assert_eq!(report.moves, 5);
+        assert_eq!(report.invalid_moves, 2);
+        host.quit().unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[..6],
+            [
+                "minesweeper 1",
+                "newgame 3 3 1 1",
+                "board",
+                "###",
+                "###",
+                "###"
+            ]
+        );
+        assert!(lines.contains(&"error Field (9, 9) is out of bounds"));
+        assert_eq!(lines[lines.len() - 2..], ["result win", "quit"]);
+    }
+
+    #[test]
+    fn host_limits_test() {
+        // Running out of moves leaves the game unfinished
+        let replies = "flag 0 0\nflag 0 0\nflag 0 0\n";
+        let mut host = Host::new(replies.as_bytes(), Vec::new());
+        let mut game = Minesweeper::from_mines(3, 3, &[(2, 0)]);
+        let report = host.play(&mut game, 3).unwrap();
+        assert_eq!(report.state, GameState::InProgress);
+        assert_eq!(report.moves, 3);
+
+        // Bots must answer with a move
+        let mut host = Host::new("hello\n".as_bytes(), Vec::new());
+        assert!(matches!(
+            host.play(&mut game, 3),
+            Err(ProtocolError::Unexpected(line)) if line == "hello"
+        ));
+        assert!(matches!(
+            host.play(&mut game, 3),
+            Err(ProtocolError::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,630 @@
+//! LAN races, players get the same seeded board and race to clear it
+//!
+//! One player hosts a [RaceServer], every player (including the host) connects to it with a
+//! [RaceClient]. Messages are single lines of text over TCP:
+//!
+//! ```text
+//! peer: hello <name>                host: welcome <id>
+//!                                   host: joined <id> <name>      (once for every player)
+//!                                   host: left <id>
+//!                                   host: start <width> <height> <mines> <seed> <x> <y>
+//! peer: board <rows>                host: board <id> <rows>       (relayed to the other players)
+//! peer: finished win|loss <millis>  host: finished <id> win|loss <millis>
+//!                                   host: winner <id>
+//! ```
+//!
+//! Board rows use the characters of [crate::protocol], separated by `/`. Everyone receives
+//! `start` at the same time and opens the same start field, so all races begin from one position.
+
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    str::{FromStr, SplitWhitespace},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{minesweeper::*, protocol};
+
+/// Settings shared by every player of a race
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaceStart {
+    pub width: u16,
+    pub height: u16,
+    pub mines: usize,
+    pub seed: u64,
+    /// Field opened for every player when the race starts
+    pub start: Position,
+}
+
+impl RaceStart {
+    /// Pick the start field for a seeded board, failing if the board cannot be generated
+    pub fn new(
+        width: u16,
+        height: u16,
+        mines: usize,
+        seed: u64,
+    ) -> Result<RaceStart, MinesweeperError> {
+        let game = Minesweeper::try_new_seeded(width, height, mines, seed)?;
+        let start = start_field(&game).expect("Generated boards have a safe field");
+        return Ok(RaceStart {
+            width,
+            height,
+            mines,
+            seed,
+            start,
+        });
+    }
+
+    /// Create the board of the race with the start field opened
+    pub fn create_game(&self) -> Result<Minesweeper, MinesweeperError> {
+        let mut game = Minesweeper::try_new_seeded(self.width, self.height, self.mines, self.seed)?;
+        game.try_open(self.start)?;
+        return Ok(game);
+    }
+}
+
+/// Get the safe field closest to the centre, preferring fields without neighboring mines
+pub fn start_field(game: &Minesweeper) -> Option<Position> {
+    let (centre_x, centre_y) = (i32::from(game.width) / 2, i32::from(game.height) / 2);
+    return (0..game.height)
+        .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+        .filter(|&pos| !game.is_mined(pos))
+        .min_by_key(|&(x, y)| {
+            let distance = (i32::from(x) - centre_x).pow(2) + (i32::from(y) - centre_y).pow(2);
+            (game.neighboring_mines((x, y)) > 0, distance)
+        });
+}
+
+/// Messages sent by the host to the players of a race
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaceMessage {
+    /// Id given to the player that just connected
+    Welcome(usize),
+    Joined(usize, String),
+    Left(usize),
+    Start(RaceStart),
+    /// Visible board of a player
+    Board(usize, Vec<String>),
+    /// A player finished, with the time taken in milliseconds
+    Finished(usize, GameState, u64),
+    /// The first player to clear the board
+    Winner(usize),
+}
+
+impl RaceMessage {
+    pub fn parse(line: &str) -> Option<RaceMessage> {
+        let (command, arguments) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let mut parts = arguments.split_whitespace();
+
+        let message = match command {
+            "welcome" => RaceMessage::Welcome(parse_next(&mut parts)?),
+            "left" => RaceMessage::Left(parse_next(&mut parts)?),
+            "winner" => RaceMessage::Winner(parse_next(&mut parts)?),
+            "joined" => {
+                let (id, name) = arguments.split_once(' ')?;
+                RaceMessage::Joined(id.parse().ok()?, name.to_string())
+            }
+            "start" => RaceMessage::Start(RaceStart {
+                width: parse_next(&mut parts)?,
+                height: parse_next(&mut parts)?,
+                mines: parse_next(&mut parts)?,
+                seed: parse_next(&mut parts)?,
+                start: (parse_next(&mut parts)?, parse_next(&mut parts)?),
+            }),
+            "board" => {
+                let (id, rows) = arguments.split_once(' ')?;
+                RaceMessage::Board(id.parse().ok()?, parse_rows(rows))
+            }
+            "finished" => RaceMessage::Finished(
+                parse_next(&mut parts)?,
+                parse_result(parts.next()?)?,
+                parse_next(&mut parts)?,
+            ),
+            _ => return None,
+        };
+        return Some(message);
+    }
+}
+
+impl Display for RaceMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RaceMessage::Welcome(id) => write!(f, "welcome {}", id),
+            RaceMessage::Joined(id, name) => write!(f, "joined {} {}", id, name),
+            RaceMessage::Left(id) => write!(f, "left {}", id),
+            RaceMessage::Start(start) => write!(
+                f,
+                "start {} {} {} {} {} {}",
+                start.width, start.height, start.mines, start.seed, start.start.0, start.start.1
+            ),
+            RaceMessage::Board(id, rows) => write!(f, "board {} {}", id, rows.join("/")),
+            RaceMessage::Finished(id, state, millis) => {
+                write!(f, "finished {} {} {}", id, format_result(*state), millis)
+            }
+            RaceMessage::Winner(id) => write!(f, "winner {}", id),
+        }
+    }
+}
+
+fn parse_next<T: FromStr>(parts: &mut SplitWhitespace) -> Option<T> {
+    return parts.next()?.parse().ok();
+}
+
+fn parse_rows(rows: &str) -> Vec<String> {
+    return rows.trim().split('/').map(String::from).collect();
+}
+
+fn parse_result(result: &str) -> Option<GameState> {
+    match result {
+        "win" => Some(GameState::Win),
+        "loss" => Some(GameState::Loss),
+        _ => None,
+    }
+}
+
+fn format_result(state: GameState) -> &'static str {
+    match state {
+        GameState::Win => "win",
+        _ => "loss",
+    }
+}
+
+// region Host
+
+#[derive(Debug)]
+struct Peer {
+    id: usize,
+    name: String,
+    stream: TcpStream,
+}
+
+#[derive(Debug, Default)]
+struct Lobby {
+    peers: Vec<Peer>,
+    next_id: usize,
+    winner: Option<usize>,
+}
+
+impl Lobby {
+    /// Send a message to every player except the one with the given id
+    fn broadcast(&mut self, message: &RaceMessage, except: Option<usize>) {
+        let line = message.to_string();
+        log::trace!("> {}", line);
+        for peer in self.peers.iter_mut().filter(|peer| Some(peer.id) != except) {
+            if let Err(error) = writeln!(peer.stream, "{}", line) {
+                log::debug!("Could not send to player {}: {}", peer.id, error);
+            }
+        }
+    }
+}
+
+/// Hosts a race, relaying progress between the connected players
+#[derive(Debug)]
+pub struct RaceServer {
+    addr: SocketAddr,
+    lobby: Arc<Mutex<Lobby>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl RaceServer {
+    /// Listen on the given address, use port 0 to pick a free port
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<RaceServer> {
+        let listener = TcpListener::bind(addr)?;
+        let server = RaceServer {
+            addr: listener.local_addr()?,
+            lobby: Arc::default(),
+            stopped: Arc::default(),
+        };
+
+        let (lobby, stopped) = (server.lobby.clone(), server.stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        let lobby = lobby.clone();
+                        thread::spawn(move || handle_peer(stream, lobby));
+                    }
+                    Err(error) => log::warn!("Could not accept player: {}", error),
+                }
+            }
+        });
+        return Ok(server);
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        return self.addr;
+    }
+
+    /// Names of the connected players by id
+    pub fn players(&self) -> Vec<(usize, String)> {
+        let lobby = self.lobby.lock().unwrap();
+        return lobby
+            .peers
+            .iter()
+            .map(|peer| (peer.id, peer.name.clone()))
+            .collect();
+    }
+
+    /// Start a race for every connected player at the same time
+    pub fn start(&self, start: RaceStart) {
+        let mut lobby = self.lobby.lock().unwrap();
+        lobby.winner = None;
+        lobby.broadcast(&RaceMessage::Start(start), None);
+        log::info!("Race started with seed {}", start.seed);
+    }
+}
+
+impl Drop for RaceServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        for peer in &self.lobby.lock().unwrap().peers {
+            peer.stream.shutdown(Shutdown::Both).ok();
+        }
+        // Wake the listener so it sees the server has stopped
+        let wake_addr = SocketAddr::new([127, 0, 0, 1].into(), self.addr.port());
+        TcpStream::connect(wake_addr).ok();
+    }
+}
+
+/// Talk to a single player until they disconnect
+fn handle_peer(stream: TcpStream, lobby: Arc<Mutex<Lobby>>) {
+    let mut reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(error) => return log::warn!("Could not read from player: {}", error),
+    };
+
+    let mut line = String::new();
+    let name = match reader.read_line(&mut line) {
+        Ok(_) => match line.trim().strip_prefix("hello ") {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => return log::debug!("Player sent '{}' instead of hello", line.trim()),
+        },
+        Err(error) => return log::debug!("Could not read hello: {}", error),
+    };
+
+    // Tell the new player who is already here, then tell everyone about them
+    let id = {
+        let mut lobby = lobby.lock().unwrap();
+        let id = lobby.next_id;
+        lobby.next_id += 1;
+
+        let mut writer = &stream;
+        let mut greeting = vec![RaceMessage::Welcome(id)];
+        greeting.extend(
+            lobby
+                .peers
+                .iter()
+                .map(|peer| RaceMessage::Joined(peer.id, peer.name.clone())),
+        );
+        for message in greeting {
+            writeln!(writer, "{}", message).ok();
+        }
+
+        match stream.try_clone() {
+            Ok(stream) => lobby.peers.push(Peer {
+                id,
+                name: name.clone(),
+                stream,
+            }),
+            Err(error) => return log::warn!("Could not write to player: {}", error),
+        }
+        lobby.broadcast(&RaceMessage::Joined(id, name.clone()), None);
+        id
+    };
+    log::info!("{} joined the race as player {}", name, id);
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        log::trace!("< {}: {}", id, line.trim_end());
+
+        let (command, arguments) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let mut lobby = lobby.lock().unwrap();
+        match command {
+            "board" => {
+                lobby.broadcast(&RaceMessage::Board(id, parse_rows(arguments)), Some(id));
+            }
+            "finished" => {
+                let mut parts = arguments.split_whitespace();
+                let state = parts.next().and_then(parse_result);
+                let millis = parts.next().and_then(|millis| millis.parse().ok());
+                let (Some(state), Some(millis)) = (state, millis) else {
+                    log::debug!("Player {} sent invalid result '{}'", id, arguments);
+                    continue;
+                };
+
+                lobby.broadcast(&RaceMessage::Finished(id, state, millis), None);
+                if state == GameState::Win && lobby.winner.is_none() {
+                    lobby.winner = Some(id);
+                    lobby.broadcast(&RaceMessage::Winner(id), None);
+                }
+            }
+            _ => log::debug!("Player {} sent unknown command '{}'", id, line.trim()),
+        }
+    }
+
+    let mut lobby = lobby.lock().unwrap();
+    lobby.peers.retain(|peer| peer.id != id);
+    lobby.broadcast(&RaceMessage::Left(id), None);
+    log::info!("{} left the race", name);
+}
+
+// endregion
+
+// region Player
+
+/// How long to wait for the host before giving up on each of its addresses, and then for its greeting
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connection of a single player to a [RaceServer]
+#[derive(Debug)]
+pub struct RaceClient {
+    id: usize,
+    stream: TcpStream,
+    messages: Receiver<RaceMessage>,
+    connected: bool,
+}
+
+impl RaceClient {
+    /// Join the race hosted at the given address
+    ///
+    /// Blocks until the host greets the player, or for up to [CONNECT_TIMEOUT] for each address and
+    /// again for the greeting, so it is best called away from the user interface
+    pub fn connect(addr: impl ToSocketAddrs, name: &str) -> io::Result<RaceClient> {
+        return Self::connect_within(addr, name, CONNECT_TIMEOUT);
+    }
+
+    fn connect_within(
+        addr: impl ToSocketAddrs,
+        name: &str,
+        timeout: Duration,
+    ) -> io::Result<RaceClient> {
+        let mut stream = Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Address did not resolve",
+        ));
+        for addr in addr.to_socket_addrs()? {
+            stream = TcpStream::connect_timeout(&addr, timeout);
+            if stream.is_ok() {
+                break;
+            }
+        }
+        let stream = stream?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = &stream;
+        writeln!(writer, "hello {}", name.replace(['\n', '\r'], " "))?;
+
+        // Something other than a race host may accept the connection and never answer
+        let mut line = String::new();
+        stream.set_read_timeout(Some(timeout))?;
+        reader.read_line(&mut line)?;
+        let Some(RaceMessage::Welcome(id)) = RaceMessage::parse(&line) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected greeting '{}'", line.trim()),
+            ));
+        };
+        stream.set_read_timeout(None)?;
+
+        // Read messages in the background, they are collected with poll
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                match RaceMessage::parse(&line) {
+                    Some(message) => {
+                        if sender.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    None => log::debug!("Ignoring unknown race message '{}'", line),
+                }
+            }
+        });
+
+        return Ok(RaceClient {
+            id,
+            stream,
+            messages,
+            connected: true,
+        });
+    }
+
+    /// Id of this player in the race
+    pub fn id(&self) -> usize {
+        return self.id;
+    }
+
+    /// Whether the host is still connected, only updated by [RaceClient::poll]
+    pub fn is_connected(&self) -> bool {
+        return self.connected;
+    }
+
+    /// Get the messages received since the last poll without blocking
+    pub fn poll(&mut self) -> Vec<RaceMessage> {
+        let mut messages = Vec::new();
+        loop {
+            match self.messages.try_recv() {
+                Ok(message) => messages.push(message),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+        return messages;
+    }
+
+    /// Share the visible board with the other players
+    pub fn send_board(&mut self, game: &Minesweeper) -> io::Result<()> {
+        return self.send(&format!("board {}", protocol::format_board(game).join("/")));
+    }
+
+    /// Report the end of this player's game
+    pub fn send_finished(&mut self, state: GameState, millis: u64) -> io::Result<()> {
+        return self.send(&format!("finished {} {}", format_result(state), millis));
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        log::trace!("> {}", line);
+        return writeln!(self.stream, "{}", line);
+    }
+}
+
+impl Drop for RaceClient {
+    fn drop(&mut self) {
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Poll until the client receives a message matching the predicate
+    fn wait_for(client: &mut RaceClient, predicate: impl Fn(&RaceMessage) -> bool) -> RaceMessage {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(message) = client.poll().into_iter().find(|message| predicate(message)) {
+                return message;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("Timed out waiting for race message");
+    }
+
+    #[test]
+    fn race_message_test() {
+        let messages = [
+            RaceMessage::Welcome(2),
+            RaceMessage::Joined(1, String::from("Player One")),
+            RaceMessage::Left(3),
+            RaceMessage::Start(RaceStart::new(9, 9, 10, 42).unwrap()),
+            RaceMessage::Board(0, vec![String::from("01#"), String::from("F*#")]),
+            RaceMessage::Finished(1, GameState::Win, 12345),
+            RaceMessage::Winner(1),
+        ];
+        for message in messages {
+            assert_eq!(RaceMessage::parse(&message.to_string()), Some(message));
+        }
+
+        assert_eq!(RaceMessage::parse("start 9 9 10"), None);
+        assert_eq!(RaceMessage::parse("finished 1 draw 10"), None);
+        assert_eq!(RaceMessage::parse("hello"), None);
+    }
+
+    #[test]
+    fn race_start_test() {
+        // Every player gets the same board, opened at the same field
+        let start = RaceStart::new(16, 16, 40, 7).unwrap();
+        let (first, second) = (start.create_game().unwrap(), start.create_game().unwrap());
+        for y in 0..start.height {
+            for x in 0..start.width {
+                assert_eq!(first.is_mined((x, y)), second.is_mined((x, y)));
+            }
+        }
+        assert_eq!(first.get_field_state(start.start), FieldState::Open(0));
+        assert_eq!(first.game_state, GameState::InProgress);
+
+        assert!(RaceStart::new(3, 3, 10, 0).is_err());
+    }
+
+    #[test]
+    fn silent_host_test() {
+        // A listener that accepts the connection but never greets the player
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let silent = thread::spawn(move || listener.accept().map(|(stream, _)| stream));
+
+        let started = Instant::now();
+        let error =
+            RaceClient::connect_within(addr, "Player", Duration::from_millis(100)).unwrap_err();
+        assert!(
+            matches!(
+                error.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ),
+            "{:?}",
+            error
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(silent.join());
+    }
+
+    #[test]
+    fn loopback_race_test() {
+        let server = RaceServer::bind("127.0.0.1:0").unwrap();
+        let mut host = RaceClient::connect(server.local_addr(), "Host").unwrap();
+        let mut guest = RaceClient::connect(server.local_addr(), "Guest").unwrap();
+        assert_ne!(host.id(), guest.id());
+
+        // Both players are announced to each other
+        let guest_id = guest.id();
+        wait_for(&mut host, |message| {
+            *message == RaceMessage::Joined(guest_id, String::from("Guest"))
+        });
+        assert_eq!(server.players().len(), 2);
+
+        // Synchronized start with the same board
+        let start = RaceStart::new(9, 9, 10, 3).unwrap();
+        server.start(start);
+        for client in [&mut host, &mut guest] {
+            assert_eq!(
+                wait_for(client, |message| matches!(message, RaceMessage::Start(_))),
+                RaceMessage::Start(start)
+            );
+        }
+
+        // Progress is relayed to the other player
+        let game = start.create_game().unwrap();
+        guest.send_board(&game).unwrap();
+        assert_eq!(
+            wait_for(&mut host, |message| matches!(
+                message,
+                RaceMessage::Board(..)
+            )),
+            RaceMessage::Board(guest_id, protocol::format_board(&game))
+        );
+
+        // The first player to win takes the race
+        guest.send_finished(GameState::Win, 1500).unwrap();
+        host.send_finished(GameState::Win, 2000).unwrap();
+        assert_eq!(
+            wait_for(&mut host, |message| matches!(
+                message,
+                RaceMessage::Winner(_)
+            )),
+            RaceMessage::Winner(guest_id)
+        );
+
+        // Leaving is announced
+        drop(guest);
+        wait_for(&mut host, |message| *message == RaceMessage::Left(guest_id));
+
+        // Stopping the server disconnects the players
+        drop(server);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while host.is_connected() && Instant::now() < deadline {
+            host.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!host.is_connected());
+    }
+}
@@ -0,0 +1,569 @@
+//! Local HTTP server exposing games as a JSON API, without the user interface
+//!
+//! | Method   | Path                        | Body                       |
+//! |----------|-----------------------------|----------------------------|
+//! | `POST`   | `/games`                    | [NewGame], all optional    |
+//! | `GET`    | `/games/{id}`               |                            |
+//! | `DELETE` | `/games/{id}`               |                            |
+//! | `POST`   | `/games/{id}/open`          | `{"x": 0, "y": 0}`         |
+//! | `POST`   | `/games/{id}/flag`          | `{"x": 0, "y": 0}`         |
+//! | `POST`   | `/games/{id}/unflag`        | `{"x": 0, "y": 0}`         |
+//! | `POST`   | `/games/{id}/chord`         | `{"x": 0, "y": 0}`         |
+//! | `GET`    | `/games/{id}/probabilities` |                            |
+//! | `GET`    | `/games/{id}/step`          |                            |
+//!
+//! Games are returned as [GameView], using the board rows from [crate::protocol::format_board].
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    minesweeper::*,
+    protocol,
+    solver::{MineChance, Solver, SolverStep, SolverStrategy},
+};
+
+/// Largest board a game can be created with, requests are handled one at a time so a huge board
+/// would hold up every other game
+pub const MAX_FIELDS: usize = 10_000;
+
+/// Options for creating a game
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NewGame {
+    pub width: u16,
+    pub height: u16,
+    pub mines: usize,
+    /// Seed for placing the mines, random if not given
+    pub seed: Option<u64>,
+    pub lives: u8,
+    pub first_click: FirstClick,
+    pub question_marks: bool,
+    pub solver_strategy: SolverStrategy,
+    /// Seed for the solver's choice between equally good guesses, the first field if not given
+    pub solver_seed: Option<u64>,
+}
+
+impl Default for NewGame {
+    fn default() -> Self {
+        Self {
+            width: 9,
+            height: 9,
+            mines: 10,
+            seed: None,
+            lives: 1,
+            first_click: FirstClick::Safe,
+            question_marks: true,
+            solver_strategy: SolverStrategy::default(),
+            solver_seed: None,
+        }
+    }
+}
+
+/// Field targeted by a move
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Target {
+    x: u16,
+    y: u16,
+}
+
+/// Player visible state of a game
+#[derive(Debug, Clone, Serialize)]
+pub struct GameView {
+    pub id: u64,
+    pub width: u16,
+    pub height: u16,
+    pub mines: usize,
+    /// Negative when more fields are flagged than there are mines
+    pub remaining_mines: isize,
+    pub lives: u8,
+    pub state: GameState,
+    /// One string per row, see [crate::protocol]
+    pub board: Vec<String>,
+}
+
+/// Result of a move that opens fields
+#[derive(Debug, Clone, Serialize)]
+struct OpenView {
+    opened: Vec<Position>,
+    game: GameView,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+struct ChanceView {
+    chance: f32,
+    /// Whether the chance comes from neighboring numbers rather than the overall mine density
+    informed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProbabilitiesView {
+    /// One list per row
+    probabilities: Vec<Vec<ChanceView>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum StepView {
+    Open { x: u16, y: u16 },
+    Flag { x: u16, y: u16 },
+    Unflag { x: u16, y: u16 },
+    None,
+}
+
+impl From<SolverStep> for StepView {
+    fn from(step: SolverStep) -> Self {
+        match step {
+            SolverStep::Open((x, y)) => StepView::Open { x, y },
+            SolverStep::Flag((x, y)) => StepView::Flag { x, y },
+            SolverStep::Unflag((x, y)) => StepView::Unflag { x, y },
+            SolverStep::None => StepView::None,
+        }
+    }
+}
+
+/// Errors returned to API clients
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    NotFound,
+    MethodNotAllowed,
+    /// The move is not valid for the game
+    Move(MinesweeperError),
+    /// The server failed to answer a valid request, e.g. the response could not be serialized
+    Internal(String),
+}
+
+impl ApiError {
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::BadRequest(_) => 400,
+            ApiError::NotFound => 404,
+            ApiError::MethodNotAllowed => 405,
+            ApiError::Move(_) => 409,
+            ApiError::Internal(_) => 500,
+        }
+    }
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiError::BadRequest(reason) => write!(f, "Bad request: {}", reason),
+            ApiError::NotFound => f.write_str("Not found"),
+            ApiError::MethodNotAllowed => f.write_str("Method not allowed"),
+            ApiError::Move(error) => write!(f, "{}", error),
+            ApiError::Internal(reason) => write!(f, "Internal error: {}", reason),
+        }
+    }
+}
+
+impl Error for ApiError {}
+
+/// Response to an API request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// JSON body
+    pub body: String,
+}
+
+#[derive(Debug)]
+struct Session {
+    game: Minesweeper,
+    solver: Solver,
+}
+
+/// Games managed by the API, independent of the HTTP transport
+#[derive(Debug, Default)]
+pub struct Api {
+    games: HashMap<u64, Session>,
+    next_id: u64,
+}
+
+impl Api {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle a request, always returning a JSON response
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> Response {
+        let result = self.route(method, path, body);
+        log::info!(
+            "{} {} -> {}",
+            method,
+            path,
+            result.as_ref().map_or_else(|error| error.status(), |_| 200)
+        );
+
+        match result {
+            Ok(body) => Response { status: 200, body },
+            Err(error) => Response {
+                status: error.status(),
+                body: serde_json::json!({ "error": error.to_string() }).to_string(),
+            },
+        }
+    }
+
+    fn route(&mut self, method: &str, path: &str, body: &str) -> Result<String, ApiError> {
+        let path = path.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match (method, segments.as_slice()) {
+            ("POST", ["games"]) => {
+                let options: NewGame = parse_body(body)?;
+                let id = self.create(&options)?;
+                return to_json(&self.view(id)?);
+            }
+            (_, ["games"]) => return Err(ApiError::MethodNotAllowed),
+            _ => {}
+        }
+
+        let (id, action) = match segments.as_slice() {
+            ["games", id] => (*id, None),
+            ["games", id, action] => (*id, Some(*action)),
+            _ => return Err(ApiError::NotFound),
+        };
+        let id: u64 = id.parse().map_err(|_| ApiError::NotFound)?;
+        if !self.games.contains_key(&id) {
+            return Err(ApiError::NotFound);
+        }
+
+        match (method, action) {
+            ("GET", None) => to_json(&self.view(id)?),
+            ("DELETE", None) => {
+                self.games.remove(&id);
+                to_json(&serde_json::json!({ "deleted": id }))
+            }
+            ("POST", Some("open")) => {
+                let target: Target = parse_body(body)?;
+                let game = &mut self.session(id)?.game;
+                let opened = match game.try_open((target.x, target.y)) {
+                    Ok(OpenResult::Cascade(opened)) => opened,
+                    Ok(_) => vec![(target.x, target.y)],
+                    Err(error) => return Err(ApiError::Move(error)),
+                };
+                game.drain_events();
+                to_json(&OpenView {
+                    opened,
+                    game: self.view(id)?,
+                })
+            }
+            ("POST", Some("flag")) => {
+                let target: Target = parse_body(body)?;
+                let game = &mut self.session(id)?.game;
+                game.try_flag((target.x, target.y))
+                    .map_err(ApiError::Move)?;
+                game.drain_events();
+                to_json(&self.view(id)?)
+            }
+            ("POST", Some("unflag")) => {
+                let target: Target = parse_body(body)?;
+                let game = &mut self.session(id)?.game;
+                game.try_clear_mark((target.x, target.y))
+                    .map_err(ApiError::Move)?;
+                game.drain_events();
+                to_json(&self.view(id)?)
+            }
+            ("POST", Some("chord")) => {
+                let target: Target = parse_body(body)?;
+                let game = &mut self.session(id)?.game;
+                let opened = game
+                    .try_chord((target.x, target.y))
+                    .map_err(ApiError::Move)?;
+                game.drain_events();
+                to_json(&OpenView {
+                    opened,
+                    game: self.view(id)?,
+                })
+            }
+            ("GET", Some("probabilities")) => {
+                let session = self.session(id)?;
+                session.solver.calculate_field(&session.game);
+                let probabilities = (0..session.game.height)
+                    .map(|y| {
+                        (0..session.game.width)
+                            .map(|x| match session.solver.get_mine_chance((x, y)) {
+                                MineChance::WithInformation(chance) => ChanceView {
+                                    chance,
+                                    informed: true,
+                                },
+                                MineChance::NoInformation(chance) => ChanceView {
+                                    chance,
+                                    informed: false,
+                                },
+                            })
+                            .collect()
+                    })
+                    .collect();
+                to_json(&ProbabilitiesView { probabilities })
+            }
+            ("GET", Some("step")) => {
+                let session = self.session(id)?;
+                let step = session.solver.solve_step(&session.game);
+                to_json(&StepView::from(step))
+            }
+            (_, None | Some("open" | "flag" | "unflag" | "chord" | "probabilities" | "step")) => {
+                Err(ApiError::MethodNotAllowed)
+            }
+            _ => Err(ApiError::NotFound),
+        }
+    }
+
+    /// Create a game, returning its id
+    fn create(&mut self, options: &NewGame) -> Result<u64, ApiError> {
+        let fields = usize::from(options.width) * usize::from(options.height);
+        if fields > MAX_FIELDS {
+            return Err(ApiError::BadRequest(format!(
+                "Board of {} fields is larger than the limit of {}",
+                fields, MAX_FIELDS
+            )));
+        }
+
+        let game = match options.seed {
+            Some(seed) => {
+                Minesweeper::try_new_seeded(options.width, options.height, options.mines, seed)
+            }
+            None => Minesweeper::try_new(options.width, options.height, options.mines),
+        }
+        .map_err(|error| ApiError::BadRequest(error.to_string()))?
+        .with_lives(options.lives.max(1))
+        .with_first_click(options.first_click)
+        .with_question_marks(options.question_marks);
+
+        let mut solver = Solver::new().with_strategy(options.solver_strategy);
+        if let Some(seed) = options.solver_seed {
+            solver = solver.with_seed(seed);
+        }
+
+        self.next_id += 1;
+        self.games.insert(self.next_id, Session { game, solver });
+        return Ok(self.next_id);
+    }
+
+    fn session(&mut self, id: u64) -> Result<&mut Session, ApiError> {
+        return self.games.get_mut(&id).ok_or(ApiError::NotFound);
+    }
+
+    fn view(&self, id: u64) -> Result<GameView, ApiError> {
+        let game = &self.games.get(&id).ok_or(ApiError::NotFound)?.game;
+        return Ok(GameView {
+            id,
+            width: game.width,
+            height: game.height,
+            mines: game.num_mines(),
+            remaining_mines: game.remaining_mines(),
+            lives: game.lives(),
+            state: game.game_state,
+            board: protocol::format_board(game),
+        });
+    }
+}
+
+/// Parse a JSON request body, treating an empty body as an empty object
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, ApiError> {
+    let body = if body.trim().is_empty() { "{}" } else { body };
+    return serde_json::from_str(body).map_err(|error| ApiError::BadRequest(error.to_string()));
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, ApiError> {
+    return serde_json::to_string(value).map_err(|error| ApiError::Internal(error.to_string()));
+}
+
+/// HTTP transport for the [Api]
+pub struct Server {
+    http: tiny_http::Server,
+    api: Api,
+}
+
+impl Server {
+    /// Listen on the given address, use port 0 to pick a free port
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Server, Box<dyn Error + Send + Sync>> {
+        return Ok(Server {
+            http: tiny_http::Server::http(addr)?,
+            api: Api::new(),
+        });
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        return self.http.server_addr().to_ip();
+    }
+
+    /// Handle requests until the listener fails
+    pub fn run(mut self) {
+        for mut request in self.http.incoming_requests() {
+            let mut body = String::new();
+            let response = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self
+                    .api
+                    .handle(request.method().as_str(), request.url(), &body),
+                Err(error) => Response {
+                    status: 400,
+                    body: serde_json::json!({ "error": error.to_string() }).to_string(),
+                },
+            };
+
+            let content_type =
+                tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("Header is valid");
+            let result = request.respond(
+                tiny_http::Response::from_string(response.body)
+                    .with_status_code(response.status)
+                    .with_header(content_type),
+            );
+            if let Err(error) = result {
+                log::warn!("Could not send response: {}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpStream,
+    };
+
+    fn json(response: &Response) -> serde_json::Value {
+        return serde_json::from_str(&response.body).unwrap();
+    }
+
+    #[test]
+    fn api_test() {
+        let mut api = Api::new();
+        let created = api.handle(
+            "POST",
+            "/games",
+            r#"{"width": 3, "height": 3, "mines": 1, "seed": 1, "first_click": "unprotected"}"#,
+        );
+        assert_eq!(created.status, 200);
+        let game = json(&created);
+        assert_eq!(game["id"], 1);
+        assert_eq!(game["state"], "in_progress");
+        assert_eq!(game["board"], serde_json::json!(["###", "###", "###"]));
+
+        // The board is seeded, so look the mine up directly
+        let mine = (0..3)
+            .flat_map(|y| (0..3).map(move |x| (x, y)))
+            .find(|&pos| api.games[&1].game.is_mined(pos))
+            .unwrap();
+        let mine_target = format!(r#"{{"x": {}, "y": {}}}"#, mine.0, mine.1);
+        let flagged = api.handle("POST", "/games/1/flag", &mine_target);
+        assert_eq!(json(&flagged)["remaining_mines"], 0);
+        let unflagged = api.handle("POST", "/games/1/unflag", &mine_target);
+        assert_eq!(json(&unflagged)["remaining_mines"], 1);
+        assert_eq!(
+            api.handle("POST", "/games/1/unflag", &mine_target).status,
+            409
+        );
+        api.handle("POST", "/games/1/flag", &mine_target);
+
+        let step = json(&api.handle("GET", "/games/1/step", ""));
+        assert_eq!(step["action"], "open");
+        let probabilities = json(&api.handle("GET", "/games/1/probabilities", ""));
+        assert_eq!(probabilities["probabilities"].as_array().unwrap().len(), 3);
+
+        // The flag accounts for the mine, so opening next to it spreads to every other field
+        let safe = if mine == (1, 1) { (0, 0) } else { (1, 1) };
+        let target = format!(r#"{{"x": {}, "y": {}}}"#, safe.0, safe.1);
+        let opened = json(&api.handle("POST", "/games/1/open", &target));
+        assert_eq!(opened["opened"].as_array().unwrap().len(), 8);
+        assert_eq!(opened["game"]["state"], "win");
+
+        // Moves after the game is over are rejected by the game
+        let again = api.handle("POST", "/games/1/open", &target);
+        assert_eq!(again.status, 409);
+        assert_eq!(json(&again)["error"], "Game is over");
+    }
+
+    #[test]
+    fn api_errors_test() {
+        let mut api = Api::new();
+        assert_eq!(api.handle("GET", "/games/1", "").status, 404);
+        assert_eq!(api.handle("GET", "/nothing", "").status, 404);
+        assert_eq!(api.handle("GET", "/games", "").status, 405);
+        assert_eq!(
+            api.handle("POST", "/games", r#"{"mines": 100}"#).status,
+            400
+        );
+        assert_eq!(api.handle("POST", "/games", "not json").status, 400);
+
+        // Defaults are used for anything not given
+        assert_eq!(api.handle("POST", "/games", "").status, 200);
+        assert_eq!(api.handle("POST", "/games/1/open", "{}").status, 400);
+        assert_eq!(api.handle("GET", "/games/1/open", "").status, 405);
+        assert_eq!(
+            api.handle("POST", "/games/1/chord", r#"{"x": 0, "y": 0}"#)
+                .status,
+            409
+        );
+        assert_eq!(api.handle("DELETE", "/games/1", "").status, 200);
+        assert_eq!(api.handle("GET", "/games/1", "").status, 404);
+
+        // Boards too large to handle without holding up other games are refused before being created
+        let oversized = api.handle("POST", "/games", r#"{"width": 65535, "height": 65535}"#);
+        assert_eq!(oversized.status, 400);
+        assert_eq!(
+            json(&oversized)["error"],
+            "Bad request: Board of 4294836225 fields is larger than the limit of 10000"
+        );
+        assert_eq!(
+            api.handle("POST", "/games", r#"{"width": 100, "height": 100}"#)
+                .status,
+            200
+        );
+
+        // A response that cannot be serialized is the server's fault, not the client's
+        let unserializable = std::collections::HashMap::from([((0, 0), 1)]);
+        let error = to_json(&unserializable).unwrap_err();
+        assert!(matches!(error, ApiError::Internal(_)));
+        assert_eq!(error.status(), 500);
+    }
+
+    #[test]
+    fn http_test() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || server.run());
+
+        // Minimal HTTP client
+        let request = |method: &str, path: &str, body: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(
+                stream,
+                "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+                method,
+                path,
+                body.len(),
+                body
+            )
+            .unwrap();
+
+            let mut reader = BufReader::new(stream);
+            let mut status = String::new();
+            reader.read_line(&mut status).unwrap();
+            let mut response = String::new();
+            reader.read_to_string(&mut response).unwrap();
+            let body = response.split("\r\n\r\n").nth(1).unwrap().to_string();
+            (status, body)
+        };
+
+        let (status, body) = request("POST", "/games", r#"{"width": 5, "height": 4, "seed": 7}"#);
+        assert!(status.starts_with("HTTP/1.1 200"));
+        let game: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(game["width"], 5);
+        assert_eq!(game["board"].as_array().unwrap().len(), 4);
+
+        let (status, _) = request("GET", "/games/2", "");
+        assert!(status.starts_with("HTTP/1.1 404"));
+    }
+}
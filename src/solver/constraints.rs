@@ -0,0 +1,972 @@
+//! Mine probabilities worked out from the visible board
+//!
+//! Closed fields next to open numbers form the frontier. Every number says how many mines its closed
+//! neighbors hold, and frontier fields linked by shared numbers are solved together as a component by
+//! enumerating each arrangement of mines that satisfies them. The closed fields away from the numbers
+//! hold whatever mines the frontier does not, so every arrangement is weighted by the number of ways
+//! the rest of the mines could be placed. This is what lets the mine count settle the endgame.
+//!
+//! The player's flags may be wrong, so they are ignored and only the numbers count.
+
+use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::minesweeper::*;
+
+/// Search steps allowed for a single component before falling back to a local estimate
+const MAX_SEARCH_STEPS: usize = 200_000;
+
+/// Largest frontier combined exactly with the global mine count, larger ones assume a mine density
+const MAX_EXACT_FRONTIER: usize = 256;
+
+/// Largest component checked for a forced guess, bigger ones are left to the player
+pub const MAX_FORCED_REGION: usize = 16;
+
+/// Mine probabilities of the closed fields
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    /// Probability of each closed field next to an open number
+    pub frontier: HashMap<Position, f64>,
+    /// Probability shared by the closed fields away from the numbers
+    pub other: f64,
+    /// Groups of fields that need a guess no other move can ever avoid, see [forced_region]
+    pub forced: Vec<Vec<Position>>,
+}
+
+/// A number on the board and the closed fields around it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Constraint {
+    /// Indices of the fields in the component
+    cells: Vec<usize>,
+    /// Mines among the fields
+    mines: i32,
+}
+
+/// Frontier fields linked by shared numbers
+///
+/// Its solutions only depend on the fields and numbers, so it doubles as the key of the cache
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Component {
+    cells: Vec<Position>,
+    constraints: Vec<Constraint>,
+}
+
+/// Every arrangement of mines in a component, counted by how many mines it uses
+#[derive(Debug, Clone)]
+struct Solutions {
+    /// Arrangements by number of mines, scaled so the largest is 1
+    counts: Vec<f64>,
+    /// Arrangements mining each field by number of mines, on the same scale as the counts
+    cell_counts: Vec<Vec<f64>>,
+}
+
+/// What a field tells the analysis, flags and question marks are closed like any other field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Closed,
+    Detonated,
+    Number(u8),
+    /// Mine shown once the game is over
+    OpenMine,
+}
+
+impl Field {
+    fn of(game: &Minesweeper, pos: Position) -> Self {
+        if game.is_detonated(pos) {
+            return Field::Detonated;
+        }
+        if !game.is_open(pos) {
+            return Field::Closed;
+        }
+        if game.is_mined(pos) {
+            return Field::OpenMine;
+        }
+        return Field::Number(game.neighboring_mines(pos));
+    }
+}
+
+/// The board the constraints of an [Analyser] were last built from
+#[derive(Debug)]
+struct Board {
+    /// [Minesweeper::id] of the game
+    game: u64,
+    width: u16,
+    height: u16,
+    mines: usize,
+    /// Every field in row order
+    fields: Vec<Field>,
+}
+
+impl Board {
+    /// Get the fields that changed on the given board, or none if it is not the same game
+    ///
+    /// Fields only ever open during a game, so any other change means the mines were moved
+    fn changes(&self, game: &Minesweeper, fields: &[Field]) -> Option<Vec<Position>> {
+        if self.game != game.id()
+            || (self.width, self.height, self.mines) != (game.width, game.height, game.num_mines())
+        {
+            return None;
+        }
+
+        let mut changed = Vec::new();
+        for (pos, (&before, &after)) in positions(game).zip(self.fields.iter().zip(fields)) {
+            if before == after {
+                continue;
+            }
+            if before != Field::Closed {
+                return None;
+            }
+            changed.push(pos);
+        }
+        return Some(changed);
+    }
+}
+
+/// Every position of the board in row order
+fn positions(game: &Minesweeper) -> impl Iterator<Item = Position> {
+    let width = game.width;
+    return (0..game.height).flat_map(move |y| (0..width).map(move |x| (x, y)));
+}
+
+/// Check whether a field is known to be a mine, detonated or proven by the numbers earlier
+///
+/// Flags are the player's guesses and may be wrong, so flagged fields count as unknown
+fn is_known_mine(game: &Minesweeper, proven: &HashSet<Position>, pos: Position) -> bool {
+    return game.is_detonated(pos) || proven.contains(&pos);
+}
+
+/// Check whether a field is closed and not known to be a mine, marked or not
+fn is_unknown(game: &Minesweeper, proven: &HashSet<Position>, pos: Position) -> bool {
+    return game.is_in_bounds(pos) && !game.is_open(pos) && !is_known_mine(game, proven, pos);
+}
+
+/// Get the unknown fields around a number and the mines among them, if it has any
+fn constraint(
+    game: &Minesweeper,
+    proven: &HashSet<Position>,
+    pos: Position,
+) -> Option<(Vec<Position>, i32)> {
+    if is_known_mine(game, proven, pos) || !game.is_open(pos) || game.is_mined(pos) {
+        return None;
+    }
+
+    let cells: Vec<Position> = game
+        .neighboring_fields_iter(pos)
+        .filter(|&neighbor| is_unknown(game, proven, neighbor))
+        .collect();
+    if cells.is_empty() {
+        return None;
+    }
+    let known = game
+        .neighboring_fields_iter(pos)
+        .filter(|&neighbor| is_known_mine(game, proven, neighbor))
+        .count();
+    return Some((cells, i32::from(game.neighboring_mines(pos)) - known as i32));
+}
+
+/// Work out the mine probability of every closed field, without reusing any earlier work
+#[cfg(test)]
+pub fn analyse(game: &Minesweeper) -> Analysis {
+    return Analyser::default()
+        .analyse(game)
+        .expect("A new analyser sees every field");
+}
+
+/// Works out mine probabilities, keeping the constraints and components between boards
+///
+/// Opening a field only changes the numbers and components around it, so only those are rebuilt and
+/// searched again, every other component keeps its solutions
+#[derive(Debug, Default)]
+pub struct Analyser {
+    /// Solutions of the components on the board
+    cache: HashMap<Component, Option<Solutions>>,
+    /// Fields the numbers proved to be mines on earlier boards of the game
+    ///
+    /// The numbers never lie, so these stay mines and can leave the search, unlike the player's flags
+    proven: HashSet<Position>,
+    /// Proven mines the numbers around them still count as unknown, rebuilt with the next change
+    newly_proven: Vec<Position>,
+    board: Option<Board>,
+    /// Unknown fields and mines left around every number with any unknown fields
+    constraints: HashMap<Position, (Vec<Position>, i32)>,
+    components: Vec<Component>,
+}
+
+impl Analyser {
+    /// Work out the mine probability of every closed field
+    ///
+    /// Returns none if no field changed since the last board, flags and question marks aside
+    pub fn analyse(&mut self, game: &Minesweeper) -> Option<Analysis> {
+        let fields: Vec<Field> = positions(game).map(|pos| Field::of(game, pos)).collect();
+        let last = self.board.replace(Board {
+            game: game.id(),
+            width: game.width,
+            height: game.height,
+            mines: game.num_mines(),
+            fields,
+        });
+        let board = self.board.as_ref().unwrap();
+        let changed = match last.and_then(|last| last.changes(game, &board.fields)) {
+            Some(changed) if changed.is_empty() => return None,
+            Some(mut changed) => {
+                changed.append(&mut self.newly_proven);
+                changed
+            }
+            // Mines proven on another game's board mean nothing here
+            None => {
+                self.cache.clear();
+                self.proven.clear();
+                self.newly_proven.clear();
+                self.constraints.clear();
+                self.components.clear();
+                positions(game).collect()
+            }
+        };
+
+        let mut known_mines = 0;
+        let mut unknown = 0;
+        for (pos, &field) in positions(game).zip(&board.fields) {
+            if field == Field::Detonated || self.proven.contains(&pos) {
+                known_mines += 1;
+            } else if field == Field::Closed {
+                unknown += 1;
+            }
+        }
+        self.update_components(game, &changed);
+
+        let mut analysis = Analysis::default();
+        let mut fallback_mines = 0.0;
+        let mut frontier_cells = 0;
+        let mut solved = Vec::new();
+        for component in &self.components {
+            frontier_cells += component.cells.len();
+            match &self.cache[component] {
+                Some(solutions) => solved.push((component, solutions)),
+                None => {
+                    // Too many arrangements to count, or no arrangement fits the numbers
+                    for &pos in &component.cells {
+                        let chance = local_chance(game, &self.proven, pos);
+                        fallback_mines += chance;
+                        analysis.frontier.insert(pos, chance);
+                    }
+                }
+            }
+        }
+
+        let remaining = (game.num_mines() as f64 - known_mines as f64 - fallback_mines)
+            .round()
+            .max(0.0) as usize;
+        let others = unknown - frontier_cells;
+        let solved_cells: usize = solved
+            .iter()
+            .map(|(component, _)| component.cells.len())
+            .sum();
+
+        let exact = solved_cells <= MAX_EXACT_FRONTIER
+            && combine_exact(&solved, remaining, others, &mut analysis);
+        if !exact {
+            combine_with_density(&solved, remaining, others, &mut analysis);
+        }
+
+        for (&pos, &chance) in &analysis.frontier {
+            if chance >= 1.0 && self.proven.insert(pos) {
+                self.newly_proven.push(pos);
+            }
+        }
+        for &pos in &self.proven {
+            analysis.frontier.insert(pos, 1.0);
+        }
+
+        let forced = solved
+            .iter()
+            .filter_map(|(component, solutions)| {
+                forced_region(game, &self.proven, &analysis, component, solutions)
+            })
+            .collect();
+        analysis.forced = forced;
+        return Some(analysis);
+    }
+
+    /// Rebuild the constraints of the numbers around the changed fields, and the components they touch
+    fn update_components(&mut self, game: &Minesweeper, changed: &[Position]) {
+        let numbers: HashSet<Position> = changed
+            .iter()
+            .flat_map(|&pos| std::iter::once(pos).chain(game.neighboring_fields_iter(pos)))
+            .filter(|&pos| game.is_in_bounds(pos))
+            .collect();
+
+        // Fields whose constraints changed, and with them every component they are part of
+        let mut dirty: HashSet<Position> = HashSet::new();
+        for &pos in &numbers {
+            if let Some((cells, _)) = self.constraints.remove(&pos) {
+                dirty.extend(cells);
+            }
+            if let Some(constraint) = constraint(game, &self.proven, pos) {
+                dirty.extend(&constraint.0);
+                self.constraints.insert(pos, constraint);
+            }
+        }
+
+        let mut previous = HashMap::new();
+        let (removed, kept) = std::mem::take(&mut self.components)
+            .into_iter()
+            .partition::<Vec<Component>, _>(|component| {
+                component.cells.iter().any(|cell| dirty.contains(cell))
+            });
+        self.components = kept;
+        for component in removed {
+            dirty.extend(&component.cells);
+            if let Some(solutions) = self.cache.remove(&component) {
+                previous.insert(component, solutions);
+            }
+        }
+
+        // Every constraint on a dirty field, in row order like a full rebuild so the components match
+        let mut rebuilt: Vec<Position> = dirty
+            .iter()
+            .flat_map(|&cell| game.neighboring_fields_iter(cell))
+            .filter(|pos| {
+                self.constraints
+                    .get(pos)
+                    .is_some_and(|(cells, _)| cells.iter().any(|cell| dirty.contains(cell)))
+            })
+            .collect();
+        rebuilt.sort_by_key(|&(x, y)| (y, x));
+        rebuilt.dedup();
+        let constraints: Vec<(Vec<Position>, i32)> = rebuilt
+            .iter()
+            .map(|pos| self.constraints[pos].clone())
+            .collect();
+
+        for component in components(&constraints) {
+            let solutions = match previous.remove(&component) {
+                Some(solutions) => solutions,
+                None => solve(&component),
+            };
+            self.cache.insert(component.clone(), solutions);
+            self.components.push(component);
+        }
+        self.components.sort_by_key(|component| component.cells[0]);
+    }
+}
+
+/// Get the uncertain fields of a component if they need a guess that no other move can avoid
+///
+/// That is when no field of the component is safe, every closed field around it is a known mine so
+/// opening elsewhere never tells anything about it, and every arrangement holds the same number of
+/// mines so the mine count cannot tell them apart either
+fn forced_region(
+    game: &Minesweeper,
+    proven: &HashSet<Position>,
+    analysis: &Analysis,
+    component: &Component,
+    solutions: &Solutions,
+) -> Option<Vec<Position>> {
+    if component.cells.len() > MAX_FORCED_REGION {
+        return None;
+    }
+    if solutions
+        .counts
+        .iter()
+        .filter(|&&count| count > 0.0)
+        .count()
+        != 1
+    {
+        return None;
+    }
+
+    let is_mine = |pos: Position| {
+        is_known_mine(game, proven, pos) || analysis.frontier.get(&pos) == Some(&1.0)
+    };
+    let mut region = Vec::new();
+    for &pos in &component.cells {
+        match analysis.frontier.get(&pos) {
+            Some(&chance) if chance <= 0.0 => return None,
+            Some(&chance) if chance >= 1.0 => {}
+            _ => region.push(pos),
+        }
+    }
+
+    let isolated = component.cells.iter().all(|&pos| {
+        game.neighboring_fields_iter(pos).all(|neighbor| {
+            game.is_open(neighbor) || is_mine(neighbor) || component.cells.contains(&neighbor)
+        })
+    });
+    return (isolated && !region.is_empty()).then_some(region);
+}
+
+/// Split the fields of the constraints into components linked by shared constraints
+fn components(constraints: &[(Vec<Position>, i32)]) -> Vec<Component> {
+    let mut cell_constraints: BTreeMap<Position, Vec<usize>> = BTreeMap::new();
+    for (i, (cells, _)) in constraints.iter().enumerate() {
+        for &cell in cells {
+            cell_constraints.entry(cell).or_default().push(i);
+        }
+    }
+
+    let mut seen: HashMap<Position, usize> = HashMap::new();
+    let mut used = vec![false; constraints.len()];
+    let mut components = Vec::new();
+    for &first in cell_constraints.keys() {
+        if seen.contains_key(&first) {
+            continue;
+        }
+
+        // Breadth first, so neighboring fields are assigned close together while searching
+        let mut cells = Vec::new();
+        let mut constraint_ids = Vec::new();
+        let mut queue = VecDeque::from([first]);
+        seen.insert(first, 0);
+        while let Some(cell) = queue.pop_front() {
+            seen.insert(cell, cells.len());
+            cells.push(cell);
+            for &id in &cell_constraints[&cell] {
+                if used[id] {
+                    continue;
+                }
+                used[id] = true;
+                constraint_ids.push(id);
+                for &other in &constraints[id].0 {
+                    if let Entry::Vacant(entry) = seen.entry(other) {
+                        entry.insert(usize::MAX);
+                        queue.push_back(other);
+                    }
+                }
+            }
+        }
+
+        let constraints = constraint_ids
+            .into_iter()
+            .map(|id| Constraint {
+                cells: constraints[id].0.iter().map(|cell| seen[cell]).collect(),
+                mines: constraints[id].1,
+            })
+            .collect();
+        components.push(Component { cells, constraints });
+    }
+    return components;
+}
+
+/// State of the search for every arrangement of mines in a component
+struct Search {
+    /// Constraints each field is part of
+    cell_constraints: Vec<Vec<usize>>,
+    /// Mines each constraint still needs
+    needed: Vec<i32>,
+    /// Fields of each constraint not yet assigned
+    unassigned: Vec<i32>,
+    mined: Vec<bool>,
+    steps: usize,
+    counts: Vec<f64>,
+    cell_counts: Vec<Vec<f64>>,
+}
+
+impl Search {
+    /// Assign the given field and every one after it, returning false if the step limit is hit
+    fn assign(&mut self, cell: usize, mines: usize) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_SEARCH_STEPS {
+            return false;
+        }
+
+        if cell == self.mined.len() {
+            self.counts[mines] += 1.0;
+            for (i, &mined) in self.mined.iter().enumerate() {
+                if mined {
+                    self.cell_counts[mines][i] += 1.0;
+                }
+            }
+            return true;
+        }
+
+        for mined in [false, true] {
+            let mine = i32::from(mined);
+            let fits = self.cell_constraints[cell].iter().all(|&c| {
+                let needed = self.needed[c] - mine;
+                needed >= 0 && needed < self.unassigned[c]
+            });
+            if !fits {
+                continue;
+            }
+
+            for &c in &self.cell_constraints[cell] {
+                self.needed[c] -= mine;
+                self.unassigned[c] -= 1;
+            }
+            self.mined[cell] = mined;
+            let finished = self.assign(cell + 1, mines + usize::from(mined));
+            for &c in &self.cell_constraints[cell] {
+                self.needed[c] += mine;
+                self.unassigned[c] += 1;
+            }
+            self.mined[cell] = false;
+
+            if !finished {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+/// Count every arrangement of mines in a component
+///
+/// Returns none if there are too many to count, or if no arrangement satisfies the numbers
+fn solve(component: &Component) -> Option<Solutions> {
+    let cells = component.cells.len();
+    let mut cell_constraints = vec![Vec::new(); cells];
+    for (i, constraint) in component.constraints.iter().enumerate() {
+        for &cell in &constraint.cells {
+            cell_constraints[cell].push(i);
+        }
+    }
+
+    let mut search = Search {
+        cell_constraints,
+        needed: component.constraints.iter().map(|c| c.mines).collect(),
+        unassigned: component
+            .constraints
+            .iter()
+            .map(|c| c.cells.len() as i32)
+            .collect(),
+        mined: vec![false; cells],
+        steps: 0,
+        counts: vec![0.0; cells + 1],
+        cell_counts: vec![vec![0.0; cells]; cells + 1],
+    };
+    if !search.assign(0, 0) {
+        return None;
+    }
+
+    let scale = search.counts.iter().cloned().fold(0.0, f64::max);
+    if scale == 0.0 {
+        return None;
+    }
+    return Some(Solutions {
+        counts: search.counts.iter().map(|count| count / scale).collect(),
+        cell_counts: search
+            .cell_counts
+            .iter()
+            .map(|counts| counts.iter().map(|count| count / scale).collect())
+            .collect(),
+    });
+}
+
+/// Add two independent distributions of mine counts, scaled so the largest is 1
+fn convolve(first: &[f64], second: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; first.len() + second.len() - 1];
+    for (i, a) in first.iter().enumerate() {
+        for (j, b) in second.iter().enumerate() {
+            result[i + j] += a * b;
+        }
+    }
+
+    let scale = result.iter().cloned().fold(0.0, f64::max);
+    if scale > 0.0 {
+        result.iter_mut().for_each(|value| *value /= scale);
+    }
+    return result;
+}
+
+/// Relative number of ways to place the rest of the mines in the other fields, by frontier mines
+fn placement_weights(remaining: usize, others: usize, len: usize) -> Vec<f64> {
+    // ln C(others, remaining - s), relative to the first valid s
+    let mut log_weights = vec![f64::NEG_INFINITY; len];
+    let first = remaining.saturating_sub(others);
+    let mut log_weight = 0.0;
+    for (s, weight) in log_weights.iter_mut().enumerate().skip(first) {
+        if s > remaining {
+            break;
+        }
+        *weight = log_weight;
+
+        // C(n, r - 1) / C(n, r) = r / (n - r + 1)
+        let rest = (remaining - s) as f64;
+        log_weight += rest.ln() - (others as f64 - rest + 1.0).ln();
+    }
+
+    let max = log_weights
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    return log_weights
+        .iter()
+        .map(|weight| (weight - max).exp())
+        .collect();
+}
+
+/// Turn the weighted arrangements of a field into a probability, keeping certainties exact
+fn probability(mined: f64, total: f64) -> f64 {
+    if mined == 0.0 {
+        return 0.0;
+    }
+    if mined == total {
+        return 1.0;
+    }
+    return mined / total;
+}
+
+/// Combine the components with the exact number of ways to place the remaining mines
+///
+/// Returns false if no combination uses a possible number of mines
+fn combine_exact(
+    solved: &[(&Component, &Solutions)],
+    remaining: usize,
+    others: usize,
+    analysis: &mut Analysis,
+) -> bool {
+    let count = solved.len();
+    let mut prefix = vec![vec![1.0]];
+    for (_, solutions) in solved {
+        prefix.push(convolve(prefix.last().unwrap(), &solutions.counts));
+    }
+    let mut suffix = vec![vec![1.0]; count + 1];
+    for i in (0..count).rev() {
+        suffix[i] = convolve(&solved[i].1.counts, &suffix[i + 1]);
+    }
+
+    let total = &prefix[count];
+    let weights = placement_weights(remaining, others, total.len());
+    let weighted: Vec<f64> = total.iter().zip(&weights).map(|(t, w)| t * w).collect();
+    let sum: f64 = weighted.iter().sum();
+    if sum == 0.0 {
+        return false;
+    }
+
+    for (i, (component, solutions)) in solved.iter().enumerate() {
+        // Weight of each mine count in this component, given every way the others can be arranged
+        let rest = convolve(&prefix[i], &suffix[i + 1]);
+        let component_weights: Vec<f64> = (0..solutions.counts.len())
+            .map(|k| {
+                rest.iter()
+                    .enumerate()
+                    .map(|(s, r)| r * weights[k + s])
+                    .sum()
+            })
+            .collect();
+
+        let total: f64 = (0..solutions.counts.len())
+            .map(|k| solutions.counts[k] * component_weights[k])
+            .sum();
+        for (j, &pos) in component.cells.iter().enumerate() {
+            let mined: f64 = (0..solutions.counts.len())
+                .map(|k| solutions.cell_counts[k][j] * component_weights[k])
+                .sum();
+            analysis.frontier.insert(pos, probability(mined, total));
+        }
+    }
+
+    // Mines left for the other fields, by number of mines on the frontier
+    let possible: Vec<usize> = (0..weighted.len())
+        .filter(|&s| weighted[s] > 0.0)
+        .map(|s| remaining - s)
+        .collect();
+    analysis.other = if others == 0 || possible.iter().all(|&rest| rest == 0) {
+        0.0
+    } else if possible.iter().all(|&rest| rest == others) {
+        1.0
+    } else {
+        let expected: f64 = (0..weighted.len())
+            .filter(|&s| weighted[s] > 0.0)
+            .map(|s| weighted[s] * (remaining - s) as f64)
+            .sum();
+        expected / sum / others as f64
+    };
+    return true;
+}
+
+/// Combine the components assuming mines are spread at an even density, for large frontiers
+fn combine_with_density(
+    solved: &[(&Component, &Solutions)],
+    remaining: usize,
+    others: usize,
+    analysis: &mut Analysis,
+) {
+    // Weight of each mine count in a component at the given density
+    let weights = |solutions: &Solutions, density: f64| -> Vec<f64> {
+        let cells = solutions.counts.len() as i32 - 1;
+        return (0..solutions.counts.len())
+            .map(|k| density.powi(k as i32) * (1.0 - density).powi(cells - k as i32))
+            .collect();
+    };
+    let expected_mines = |solutions: &Solutions, density: f64| -> f64 {
+        let weights = weights(solutions, density);
+        let total: f64 = (0..weights.len())
+            .map(|k| solutions.counts[k] * weights[k])
+            .sum();
+        let mines: f64 = (0..weights.len())
+            .map(|k| solutions.counts[k] * weights[k] * k as f64)
+            .sum();
+        return if total > 0.0 { mines / total } else { 0.0 };
+    };
+
+    // Settle on a density where the frontier and the other fields share the remaining mines
+    let frontier_cells: usize = solved
+        .iter()
+        .map(|(component, _)| component.cells.len())
+        .sum();
+    let mut density = remaining as f64 / (others + frontier_cells).max(1) as f64;
+    if others > 0 {
+        for _ in 0..10 {
+            let frontier_mines: f64 = solved
+                .iter()
+                .map(|(_, solutions)| expected_mines(solutions, density))
+                .sum();
+            density = ((remaining as f64 - frontier_mines) / others as f64).clamp(0.0, 1.0);
+        }
+    }
+    let density = density.clamp(0.0, 1.0);
+
+    for (component, solutions) in solved {
+        let weights = weights(solutions, density);
+        let total: f64 = (0..weights.len())
+            .map(|k| solutions.counts[k] * weights[k])
+            .sum();
+        for (j, &pos) in component.cells.iter().enumerate() {
+            let mined: f64 = (0..weights.len())
+                .map(|k| solutions.cell_counts[k][j] * weights[k])
+                .sum();
+            let chance = if total > 0.0 {
+                probability(mined, total)
+            } else {
+                local_chance_of(solutions, j)
+            };
+            analysis.frontier.insert(pos, chance);
+        }
+    }
+    analysis.other = if others > 0 { density } else { 0.0 };
+}
+
+/// Unweighted share of arrangements mining a field
+fn local_chance_of(solutions: &Solutions, cell: usize) -> f64 {
+    let total: f64 = solutions.counts.iter().sum();
+    let mined: f64 = solutions
+        .cell_counts
+        .iter()
+        .map(|counts| counts[cell])
+        .sum();
+    return probability(mined, total);
+}
+
+/// Estimate the chance of a field from its neighboring numbers alone, averaging what each suggests
+fn local_chance(game: &Minesweeper, proven: &HashSet<Position>, pos: Position) -> f64 {
+    let chances: Vec<f64> = game
+        .neighboring_fields_iter(pos)
+        .filter(|&neighbor| {
+            game.is_in_bounds(neighbor) && game.is_open(neighbor) && !game.is_mined(neighbor)
+        })
+        .map(|neighbor| {
+            let mines = game.neighboring_mines(neighbor) as f64;
+            let known = game
+                .neighboring_fields_iter(neighbor)
+                .filter(|&field| is_known_mine(game, proven, field))
+                .count() as f64;
+            let closed = game
+                .neighboring_fields_iter(neighbor)
+                .filter(|&field| is_unknown(game, proven, field))
+                .count() as f64;
+            ((mines - known) / closed).clamp(0.0, 1.0)
+        })
+        .collect();
+
+    return chances.iter().sum::<f64>() / chances.len().max(1) as f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exact probabilities by trying every placement of the remaining mines
+    fn brute_force(game: &Minesweeper) -> HashMap<Position, f64> {
+        let positions: Vec<Position> = (0..game.height)
+            .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+            .collect();
+        let unknown: Vec<Position> = positions
+            .iter()
+            .cloned()
+            .filter(|&pos| is_unknown(game, &HashSet::new(), pos))
+            .collect();
+        let known = positions
+            .iter()
+            .filter(|&&pos| is_known_mine(game, &HashSet::new(), pos))
+            .count();
+        let numbers: Vec<Position> = positions
+            .iter()
+            .cloned()
+            .filter(|&pos| game.is_open(pos) && !game.is_mined(pos))
+            .collect();
+
+        fn place(
+            game: &Minesweeper,
+            unknown: &[Position],
+            numbers: &[Position],
+            mines: &mut Vec<Position>,
+            start: usize,
+            remaining: usize,
+            counts: &mut (f64, HashMap<Position, f64>),
+        ) {
+            if remaining == 0 {
+                let valid = numbers.iter().all(|&number| {
+                    let around = game
+                        .neighboring_fields_iter(number)
+                        .filter(|neighbor| {
+                            is_known_mine(game, &HashSet::new(), *neighbor)
+                                || mines.contains(neighbor)
+                        })
+                        .count();
+                    around == usize::from(game.neighboring_mines(number))
+                });
+                if valid {
+                    counts.0 += 1.0;
+                    for mine in mines.iter() {
+                        *counts.1.entry(*mine).or_default() += 1.0;
+                    }
+                }
+                return;
+            }
+            for i in start..unknown.len() {
+                mines.push(unknown[i]);
+                place(game, unknown, numbers, mines, i + 1, remaining - 1, counts);
+                mines.pop();
+            }
+        }
+
+        let mut counts = (0.0, HashMap::new());
+        let remaining = game.num_mines() - known;
+        place(
+            game,
+            &unknown,
+            &numbers,
+            &mut Vec::new(),
+            0,
+            remaining,
+            &mut counts,
+        );
+        return unknown
+            .iter()
+            .map(|&pos| (pos, counts.1.get(&pos).copied().unwrap_or(0.0) / counts.0))
+            .collect();
+    }
+
+    #[test]
+    fn brute_force_test() {
+        // Compare every closed field against counting all placements of the mines
+        let mut checked = 0;
+        for seed in 0..40 {
+            let mut game = Minesweeper::new_seeded(5, 4, 5, seed);
+            let safe: Vec<Position> = (0..game.height)
+                .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+                .filter(|&pos| !game.is_mined(pos))
+                .collect();
+
+            // Check after the first open and again once more of the board is known
+            for pos in [safe[safe.len() / 2], safe[0]] {
+                game.open(pos);
+                if game.game_state != GameState::InProgress {
+                    break;
+                }
+
+                let analysis = analyse(&game);
+                for (pos, expected) in brute_force(&game) {
+                    let chance = analysis
+                        .frontier
+                        .get(&pos)
+                        .copied()
+                        .unwrap_or(analysis.other);
+                    assert!(
+                        (chance - expected).abs() < 1e-9,
+                        "Seed {}, field ({}, {}) has chance {} instead of {}",
+                        seed,
+                        pos.0,
+                        pos.1,
+                        chance,
+                        expected
+                    );
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > 40);
+    }
+
+    #[test]
+    fn mine_count_test() {
+        // The number shows the last mine is next to it, so the fields away from it are safe
+        let mut game = Minesweeper::from_mines(6, 1, &[(2, 0)]);
+        game.open((5, 0));
+        let analysis = analyse(&game);
+        assert_eq!(analysis.frontier[&(2, 0)], 1.0);
+        assert_eq!(analysis.other, 0.0);
+
+        // One mine left between the two fields away from the number
+        let mut game = Minesweeper::from_mines(6, 1, &[(0, 0), (2, 0)]);
+        game.open((5, 0));
+        let analysis = analyse(&game);
+        assert_eq!(analysis.frontier[&(2, 0)], 1.0);
+        assert_eq!(analysis.other, 0.5);
+
+        // Without information every closed field is equally likely
+        let game = Minesweeper::from_mines(9, 9, &[(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(analyse(&game).other, 3.0 / 81.0);
+    }
+
+    #[test]
+    fn cache_test() {
+        // Reusing components and proven mines between boards gives the same chances as starting over
+        let mut game = Minesweeper::new_seeded(16, 16, 40, 3);
+        let mut analyser = Analyser::default();
+        let safe: Vec<Position> = (0..game.height)
+            .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+            .filter(|&pos| !game.is_mined(pos))
+            .collect();
+
+        for &pos in safe.iter().step_by(7) {
+            game.open(pos);
+            let Some(analysis) = analyser.analyse(&game) else {
+                continue;
+            };
+            let expected = analyse(&game);
+            assert_eq!(analysis.frontier.len(), expected.frontier.len());
+            for (pos, chance) in &expected.frontier {
+                assert!((analysis.frontier[pos] - chance).abs() < 1e-9);
+            }
+            assert!((analysis.other - expected.other).abs() < 1e-9);
+            assert!(analyser.cache.len() <= expected.frontier.len());
+
+            // Only the components around the opened fields were rebuilt, the rest were kept as they were
+            let mut proven = analyser.proven.clone();
+            for pos in &analyser.newly_proven {
+                proven.remove(pos);
+            }
+            let constraints: Vec<(Vec<Position>, i32)> = positions(&game)
+                .filter_map(|pos| constraint(&game, &proven, pos))
+                .collect();
+            assert_eq!(analyser.components, components(&constraints));
+        }
+    }
+
+    #[test]
+    fn unchanged_test() {
+        // Marks are not trusted, so a board that only differs by them is not analysed again
+        let mut game = Minesweeper::new_seeded(9, 9, 10, 1).with_question_marks(true);
+        let mut analyser = Analyser::default();
+        game.open((4, 4));
+        assert!(analyser.analyse(&game).is_some());
+        assert!(analyser.analyse(&game).is_none());
+
+        let closed = positions(&game).find(|&pos| !game.is_open(pos)).unwrap();
+        game.flag(closed);
+        assert!(analyser.analyse(&game).is_none());
+
+        // A new game starts over, even with the same board in view
+        let mut same = Minesweeper::new_seeded(9, 9, 10, 1).with_question_marks(true);
+        same.open((4, 4));
+        assert!(analyser.analyse(&same).is_some());
+
+        // As does another game of the same size
+        let mut game = Minesweeper::new_seeded(9, 9, 10, 2);
+        game.open((0, 0));
+        let analysis = analyser.analyse(&game).unwrap();
+        let expected = analyse(&game);
+        assert_eq!(analysis.frontier, expected.frontier);
+    }
+}
@@ -0,0 +1,822 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use crate::{minesweeper::*, protocol};
+
+mod constraints;
+pub mod rating;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SolverStep {
+    Open(Position),
+    Flag(Position),
+    /// Clear a flag or question mark, e.g. a flag the numbers show is wrong
+    Unflag(Position),
+    None,
+}
+
+impl Display for SolverStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverStep::None => write!(f, "None")?,
+            SolverStep::Flag(pos) => write!(f, "Flag ({}, {})", pos.0, pos.1)?,
+            SolverStep::Open(pos) => write!(f, "Open ({}, {})", pos.0, pos.1)?,
+            SolverStep::Unflag(pos) => write!(f, "Unflag ({}, {})", pos.0, pos.1)?,
+        };
+
+        Ok(())
+    }
+}
+
+/// What the solver does when no field is certain to be safe or mined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SolverStrategy {
+    /// Guess among the fields least likely to be a mine, preferring those that reveal the most
+    #[default]
+    Guess,
+    /// Open the field least likely to be a mine
+    SafestGuess,
+    /// Only make moves that are certain, stopping when a guess would be needed
+    SafeOnly,
+}
+
+impl Display for SolverStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolverStrategy::Guess => f.write_str("Guess"),
+            SolverStrategy::SafestGuess => f.write_str("Safest guess"),
+            SolverStrategy::SafeOnly => f.write_str("Safe only"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MineChance {
+    WithInformation(f32),
+    NoInformation(f32),
+}
+
+impl MineChance {
+    /// Get the probability, whether or not it is based on information
+    pub fn value(&self) -> f32 {
+        match self {
+            MineChance::WithInformation(chance) | MineChance::NoInformation(chance) => *chance,
+        }
+    }
+}
+
+impl Display for MineChance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MineChance::NoInformation(chance) => write!(f, "{:.0}%?", chance * 100.0)?,
+            MineChance::WithInformation(chance) => write!(f, "{:.0}%", chance * 100.0)?,
+        };
+
+        Ok(())
+    }
+}
+
+/// Works out mine chances and moves for a game
+///
+/// The analysis of a game is kept and updated as it is played, a different game starts it over
+#[derive(Debug)]
+pub struct Solver {
+    field: HashMap<Position, MineChance>,
+    strategy: SolverStrategy,
+    /// Seed for choosing between equally good guesses, the first in row order if not set
+    seed: Option<u64>,
+    analyser: constraints::Analyser,
+    /// Groups of fields that need a guess no other move can avoid
+    forced: Vec<Vec<Position>>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    /// Guesses within this of the safest chance count as equally safe, the one revealing more wins
+    const GUESS_TOLERANCE: f32 = 0.001;
+
+    pub fn new() -> Self {
+        Self {
+            field: HashMap::new(),
+            strategy: SolverStrategy::default(),
+            seed: None,
+            analyser: constraints::Analyser::default(),
+            forced: Vec::new(),
+        }
+    }
+
+    /// Set what the solver does when a guess is needed
+    pub fn with_strategy(mut self, strategy: SolverStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Break ties between equally good guesses randomly, the same seed and board give the same move
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn solve_step(&mut self, game: &Minesweeper) -> SolverStep {
+        if game.game_state != GameState::InProgress {
+            return SolverStep::None;
+        }
+
+        self.calculate_field(game);
+
+        // Default action is to do nothing
+        let mut action = SolverStep::None;
+
+        // Make the game opening move, go for the same corner every time unless it was already hit
+        if !game.has_started() && !game.is_detonated((0, 0)) {
+            return SolverStep::Open((0, 0));
+        }
+
+        // Logic for fields with concerete information, then for fields without
+        // These take lower priority compared to those with information
+        // Fields are visited in row order so the same board always gives the same move
+        for informed in [true, false] {
+            for pos in Self::positions(game) {
+                let (probability, is_informed) = match self.get_mine_chance(pos) {
+                    MineChance::WithInformation(probability) => (probability, true),
+                    MineChance::NoInformation(probability) => (probability, false),
+                };
+                if is_informed != informed {
+                    continue;
+                }
+
+                // Flag any fields with 100% chance that are not already flagged
+                if probability >= 1.0 && !game.is_flagged(pos) && !game.is_detonated(pos) {
+                    log::info!(
+                        "Solver suggests flagging field ({}, {}), guaranteed mine",
+                        pos.0,
+                        pos.1
+                    );
+                    action = SolverStep::Flag(pos);
+                    break;
+                }
+
+                // Open any fields with 0% chance
+                if probability <= 0.0 && !game.is_open(pos) {
+                    log::info!(
+                        "Solver suggests opening field ({}, {}), guaranteed safe",
+                        pos.0,
+                        pos.1
+                    );
+                    action = SolverStep::Open(pos);
+                    break;
+                }
+            }
+
+            if action != SolverStep::None {
+                break;
+            }
+        }
+
+        // We must guess!
+        if self.strategy == SolverStrategy::SafeOnly && action == SolverStep::None {
+            log::info!("Solver found no guaranteed move and will not guess");
+            return action;
+        }
+        if let (Some(pos), SolverStep::None) = (self.choose_guess(game), action) {
+            let chance = self.get_mine_chance(pos);
+            log::info!(
+                "Solver suggests opening field ({}, {}), best guess with chance {}",
+                pos.0,
+                pos.1,
+                chance
+            );
+            action = SolverStep::Open(pos);
+        }
+
+        // Opening a field spreads to its neighbors when it has as many flags around it as mines,
+        // so a wrong flag next to it could open a mine, take back any flag that is not certain first
+        if let SolverStep::Open(pos) = action {
+            let doubtful = game.neighboring_fields_iter(pos).find(|&neighbor| {
+                game.is_in_bounds(neighbor)
+                    && game.is_flagged(neighbor)
+                    && self.get_mine_chance(neighbor).value() < 1.0
+            });
+            if let Some(neighbor) = doubtful {
+                log::info!(
+                    "Solver suggests clearing the flag on field ({}, {}) before '{}', it may be wrong",
+                    neighbor.0,
+                    neighbor.1,
+                    action
+                );
+                action = SolverStep::Unflag(neighbor);
+            }
+        }
+
+        // Marks block opening, and with question marks enabled flagging one only clears it
+        // So clear the mark first, the intended move follows on the next step
+        // This is also how wrong flags are taken back, as the numbers show those fields are safe
+        if let SolverStep::Open(pos) | SolverStep::Flag(pos) = action {
+            if game.is_flagged(pos) || game.is_question(pos) {
+                log::info!(
+                    "Solver suggests clearing the mark on field ({}, {}) before '{}'",
+                    pos.0,
+                    pos.1,
+                    action
+                );
+                action = SolverStep::Unflag(pos);
+            }
+        }
+
+        if action == SolverStep::None {
+            log::info!("Solver suggests no action");
+        }
+        return action;
+    }
+
+    /// Pick the field to open when no field is certain to be safe
+    ///
+    /// Fields close to the lowest mine chance are compared by how likely they are to make progress,
+    /// ties go to the first field in row order, or a seeded random one if the solver has a seed
+    fn choose_guess(&self, game: &Minesweeper) -> Option<Position> {
+        // Leave the player's flags alone unless only flagged fields are left
+        let mut candidates: Vec<(Position, f32)> = Self::positions(game)
+            .filter(|&pos| !game.is_open(pos) && !game.is_detonated(pos))
+            .map(|pos| (pos, self.get_mine_chance(pos).value()))
+            .collect();
+        if candidates.iter().any(|&(pos, _)| !game.is_flagged(pos)) {
+            candidates.retain(|&(pos, _)| !game.is_flagged(pos));
+        }
+        let safest = candidates
+            .iter()
+            .map(|&(_, chance)| chance)
+            .fold(f32::INFINITY, f32::min);
+
+        let scored: Vec<(Position, f32)> = if self.strategy == SolverStrategy::SafestGuess {
+            candidates
+                .iter()
+                .map(|&(pos, chance)| (pos, 1.0 - chance))
+                .collect()
+        } else {
+            candidates
+                .iter()
+                .filter(|&&(_, chance)| chance <= safest + Self::GUESS_TOLERANCE)
+                .map(|&(pos, chance)| {
+                    (
+                        pos,
+                        (1.0 - chance) * (1.0 + self.progress_chance(game, pos)),
+                    )
+                })
+                .collect()
+        };
+        let best_score = scored
+            .iter()
+            .map(|&(_, score)| score)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let best: Vec<Position> = scored
+            .iter()
+            .filter(|&&(_, score)| score == best_score)
+            .map(|&(pos, _)| pos)
+            .collect();
+
+        if let (Some(seed), true) = (self.seed, best.len() > 1) {
+            let mut rng = StdRng::seed_from_u64(seed ^ Self::board_hash(game));
+            return best.choose(&mut rng).copied();
+        }
+        return best.first().copied();
+    }
+
+    /// Every position of the board in row order
+    fn positions(game: &Minesweeper) -> impl Iterator<Item = Position> {
+        let width = game.width;
+        return (0..game.height).flat_map(move |y| (0..width).map(move |x| (x, y)));
+    }
+
+    /// Hash of what the player can see, so seeded guesses only depend on the board and the seed
+    ///
+    /// FNV-1a, which unlike the standard library hasher is guaranteed to stay the same
+    fn board_hash(game: &Minesweeper) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for pos in Self::positions(game) {
+            hash ^= u64::from(protocol::field_char(game.get_field_state(pos)) as u32);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        return hash;
+    }
+
+    /// Estimate how likely opening a safe field is to make progress
+    ///
+    /// Combines the chance of every closed neighbor being safe, so the field cascades,
+    /// with the share of neighbors still undecided, which the revealed number tells us about
+    fn progress_chance(&self, game: &Minesweeper, pos: Position) -> f32 {
+        let mut cascade = 1.0;
+        let mut neighbors = 0;
+        let mut undecided = 0;
+        for neighbor in game.neighboring_fields_iter(pos) {
+            if !game.is_in_bounds(neighbor) {
+                continue;
+            }
+            neighbors += 1;
+
+            if game.is_flagged(neighbor) || game.is_detonated(neighbor) {
+                cascade = 0.0;
+            } else if !game.is_open(neighbor) {
+                let chance = self.get_mine_chance(neighbor);
+                cascade *= 1.0 - chance.value();
+                if let MineChance::WithInformation(value) = chance {
+                    if value > 0.0 && value < 1.0 {
+                        undecided += 1;
+                    }
+                }
+            }
+        }
+
+        return cascade + undecided as f32 / neighbors.max(1) as f32;
+    }
+
+    /// Get the probability (0-1) that the field with the given position is a mine
+    ///
+    /// Or 0 if not set
+    pub fn get_mine_chance(&self, pos: Position) -> MineChance {
+        if let Some(chance) = self.field.get(&pos) {
+            return *chance;
+        }
+
+        return MineChance::NoInformation(0.0);
+    }
+
+    /// Get every group of fields that needs a guess no other move can avoid, e.g. a 50/50 in a corner
+    ///
+    /// No field of a group is safe, and opening fields elsewhere or counting mines will never tell
+    /// anything about it, so the guess may as well be made now. Calculated with [Solver::calculate_field]
+    pub fn forced_guesses(&self) -> &[Vec<Position>] {
+        return &self.forced;
+    }
+
+    /// Get the forced guess the given field is part of, if any
+    pub fn forced_guess(&self, pos: Position) -> Option<&[Position]> {
+        return self
+            .forced
+            .iter()
+            .find(|region| region.contains(&pos))
+            .map(|region| region.as_slice());
+    }
+
+    /// Move the mines of a forced guess so that opening the given field is safe
+    ///
+    /// The mines are only rearranged within the group, into another arrangement that fits every number
+    /// on the board, so nothing the player has seen changes. Two player games are left alone, as finding a
+    /// mine scores there. Returns whether any mine was moved
+    pub fn resolve_forced_guess(&mut self, game: &mut Minesweeper, pos: Position) -> bool {
+        self.calculate_field(game);
+        if game.is_two_player() || !game.is_mined(pos) || game.is_open(pos) {
+            return false;
+        }
+        let Some(region) = self.forced_guess(pos).map(|region| region.to_vec()) else {
+            return false;
+        };
+
+        let mines: Vec<Position> = region
+            .iter()
+            .cloned()
+            .filter(|&field| game.is_mined(field))
+            .collect();
+        let mut numbers: Vec<Position> = region
+            .iter()
+            .flat_map(|&field| game.neighboring_fields_iter(field))
+            .filter(|&neighbor| game.is_open(neighbor) && !game.is_mined(neighbor))
+            .collect();
+        numbers.sort();
+        numbers.dedup();
+
+        // Every number must see as many mines with the new arrangement as it shows now
+        let fits = |arrangement: &[Position]| {
+            numbers.iter().all(|&number| {
+                let around = game
+                    .neighboring_fields_iter(number)
+                    .filter(|neighbor| {
+                        if region.contains(neighbor) {
+                            arrangement.contains(neighbor)
+                        } else {
+                            game.is_mined(*neighbor)
+                        }
+                    })
+                    .count();
+                around == usize::from(game.neighboring_mines(number))
+            })
+        };
+        let fields: Vec<Position> = region
+            .iter()
+            .cloned()
+            .filter(|&field| field != pos)
+            .collect();
+        let Some(arrangement) = find_arrangement(&fields, mines.len(), &mut Vec::new(), &fits)
+        else {
+            return false;
+        };
+
+        let from = mines.iter().filter(|field| !arrangement.contains(field));
+        let to = arrangement.iter().filter(|field| !mines.contains(field));
+        for (&from, &to) in from.zip(to) {
+            game.move_mine(from, to);
+        }
+        log::info!(
+            "Moved the mines of the forced guess at ({}, {}) so it is safe",
+            pos.0,
+            pos.1
+        );
+        return true;
+    }
+
+    /// Calculate the mine chance of every field, read back with [Solver::get_mine_chance]
+    ///
+    /// Does nothing if no field has opened since the last calculation, the chances only come from the
+    /// numbers so marking fields does not change any of them
+    pub fn calculate_field(&mut self, game: &Minesweeper) {
+        let Some(mut analysis) = self.analyser.analyse(game) else {
+            return;
+        };
+        self.forced = std::mem::take(&mut analysis.forced);
+
+        // Clear the field
+        self.field.clear();
+
+        // Calculate chance for every position in the game
+        for y in 0..game.height {
+            for x in 0..game.width {
+                let pos: Position = (x, y);
+                let mine_chance = Self::calculate_mine_chance(game, &analysis, pos);
+                self.field.insert(pos, mine_chance);
+            }
+        }
+    }
+
+    // Get the chance
+    fn calculate_mine_chance(
+        game: &Minesweeper,
+        analysis: &constraints::Analysis,
+        pos: Position,
+    ) -> MineChance {
+        // If already open, chance is 0
+        if game.is_open(pos) {
+            // Don't return 0 if the field is mined
+            // So we can review why the sovler lost the game
+            if !game.is_mined(pos) {
+                return MineChance::WithInformation(0.0);
+            }
+        }
+
+        // If already detonated, chance is 100%
+        // Flags are not trusted, they get the chance the numbers give them like any closed field
+        if game.is_detonated(pos) {
+            return MineChance::WithInformation(1.0);
+        }
+
+        // Fields next to a number are worked out from the numbers around them
+        if let Some(&chance) = analysis.frontier.get(&pos) {
+            return MineChance::WithInformation(to_chance(chance));
+        }
+
+        // Otherwise the field shares the mines left over by the frontier
+        return MineChance::NoInformation(to_chance(analysis.other));
+    }
+}
+
+/// Find the first way to place the given number of mines among the fields that fits, in order
+fn find_arrangement(
+    fields: &[Position],
+    mines: usize,
+    chosen: &mut Vec<Position>,
+    fits: &dyn Fn(&[Position]) -> bool,
+) -> Option<Vec<Position>> {
+    if chosen.len() == mines {
+        return fits(chosen).then(|| chosen.clone());
+    }
+
+    for (i, &field) in fields.iter().enumerate() {
+        chosen.push(field);
+        let found = find_arrangement(&fields[i + 1..], mines, chosen, fits);
+        chosen.pop();
+        if found.is_some() {
+            return found;
+        }
+    }
+    return None;
+}
+
+/// Convert a probability, only rounding to 0 or 1 when it is certain
+fn to_chance(probability: f64) -> f32 {
+    if probability <= 0.0 || probability >= 1.0 {
+        return probability.clamp(0.0, 1.0) as f32;
+    }
+    return (probability as f32).clamp(f32::MIN_POSITIVE, 1.0 - f32::EPSILON);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run solver steps until the solver stops or the step limit is reached
+    fn play(game: &mut Minesweeper, solver: &mut Solver, max_steps: usize) {
+        for _ in 0..max_steps {
+            match solver.solve_step(game) {
+                SolverStep::Open(pos) => {
+                    game.open(pos);
+                }
+                SolverStep::Flag(pos) => game.flag(pos),
+                SolverStep::Unflag(pos) => game.clear_mark(pos),
+                SolverStep::None => return,
+            }
+        }
+    }
+
+    #[test]
+    fn question_mark_test() {
+        for question_marks in [true, false] {
+            // A single mine in the corner, the rest of the board is solvable from the first open
+            let mut game =
+                Minesweeper::from_mines(4, 4, &[(3, 3)]).with_question_marks(question_marks);
+            game.flag((3, 3));
+            game.flag((3, 3));
+            game.flag((3, 2));
+            game.flag((3, 2));
+
+            let mut solver = Solver::new();
+            play(&mut game, &mut solver, 20);
+            assert_eq!(game.game_state, GameState::Win);
+            assert!(!game.is_question((3, 2)));
+        }
+    }
+
+    #[test]
+    fn guess_test() {
+        let game = Minesweeper::from_mines(5, 5, &[(2, 2), (4, 0), (0, 4)]);
+        let mut solver = Solver::new();
+        solver.calculate_field(&game);
+
+        // Every field is equally likely, corners have the fewest neighbors to be mines
+        let corner = solver.progress_chance(&game, (0, 0));
+        let edge = solver.progress_chance(&game, (2, 0));
+        let middle = solver.progress_chance(&game, (2, 1));
+        assert!(corner > edge && edge > middle);
+
+        for strategy in [SolverStrategy::Guess, SolverStrategy::SafestGuess] {
+            let mut solver = Solver::new().with_strategy(strategy);
+            assert_eq!(solver.solve_step(&game), SolverStep::Open((0, 0)));
+        }
+    }
+
+    /// Build a board from rows of `*` hidden mine, `.` hidden field, `F` flagged mine, `o` open field,
+    /// and `f` or `q` for a safe field wrongly flagged or question marked
+    ///
+    /// The board the player sees is checked against `visible`, in the characters of [protocol::format_board]
+    fn board(rows: &[&str], visible: &[&str]) -> Minesweeper {
+        let mut mines = Vec::new();
+        let mut opens = Vec::new();
+        let mut flags = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let pos = (x as u16, y as u16);
+                match c {
+                    '*' => mines.push(pos),
+                    'F' => {
+                        mines.push(pos);
+                        flags.push(pos);
+                    }
+                    'o' => opens.push(pos),
+                    'f' => flags.push(pos),
+                    'q' => {
+                        flags.push(pos);
+                        flags.push(pos);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut game = Minesweeper::from_mines(rows[0].len() as u16, rows.len() as u16, &mines);
+        for pos in opens {
+            game.open(pos);
+        }
+        for pos in flags {
+            game.flag(pos);
+        }
+        assert_eq!(protocol::format_board(&game), visible);
+        return game;
+    }
+
+    #[test]
+    fn regression_test() {
+        let cases = [
+            // Not started, open the corner
+            (
+                board(&["...", "...", "..*"], &["###", "###", "###"]),
+                SolverStep::Open((0, 0)),
+            ),
+            // Both fields next to the twos are mines, flagged in row order
+            (
+                board(&["o*..", "o*.."], &["2###", "2###"]),
+                SolverStep::Flag((1, 0)),
+            ),
+            // All mines are flagged, so the rest is safe by the mine count
+            (
+                board(&["oF..", "oF.."], &["2F##", "2F##"]),
+                SolverStep::Open((2, 0)),
+            ),
+            // The numbers show the flag on the right is wrong, so it is taken back
+            (board(&["oFf"], &["1FF"]), SolverStep::Unflag((2, 0))),
+            // Question marks are cleared before opening
+            (board(&["oFq"], &["1F?"]), SolverStep::Unflag((2, 0))),
+            // Only the middle field satisfies all three ones, the safe fields come first
+            (
+                board(&["ooo", ".*."], &["111", "###"]),
+                SolverStep::Open((0, 1)),
+            ),
+            // No safe move, the free corners are the best guesses and the first one wins
+            (
+                board(
+                    &["o....", ".*...", ".....", "...*.", "....*"],
+                    &["1####", "#####", "#####", "#####", "#####"],
+                ),
+                SolverStep::Open((4, 0)),
+            ),
+        ];
+
+        for (game, expected) in cases {
+            for _ in 0..3 {
+                assert_eq!(Solver::new().solve_step(&game), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn seed_test() {
+        let game = board(
+            &["o....", ".*...", ".....", "...*.", "....*"],
+            &["1####", "#####", "#####", "#####", "#####"],
+        );
+        let corners = [
+            SolverStep::Open((4, 0)),
+            SolverStep::Open((0, 4)),
+            SolverStep::Open((4, 4)),
+        ];
+
+        let mut chosen = Vec::new();
+        for seed in 0..20 {
+            let step = Solver::new().with_seed(seed).solve_step(&game);
+            assert!(corners.contains(&step));
+            assert_eq!(Solver::new().with_seed(seed).solve_step(&game), step);
+            chosen.push(step);
+        }
+        assert!(corners.iter().all(|corner| chosen.contains(corner)));
+    }
+
+    #[test]
+    fn incremental_test() {
+        // A solver kept over the game makes the same moves as a new one for every board
+        for seed in 0..3 {
+            let mut game = Minesweeper::new_seeded(16, 16, 40, seed).with_question_marks(false);
+            let mut solver = Solver::new();
+            for _ in 0..100 {
+                let step = solver.solve_step(&game);
+                assert_eq!(step, Solver::new().solve_step(&game));
+                match step {
+                    SolverStep::Open(pos) => {
+                        game.open(pos);
+                    }
+                    SolverStep::Flag(pos) => game.flag(pos),
+                    SolverStep::Unflag(pos) => game.clear_mark(pos),
+                    SolverStep::None => break,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn new_game_test() {
+        // The same solver kept across games of the same size answers as a new one would
+        let mut solver = Solver::new();
+        for seed in 0..4 {
+            let mut game = Minesweeper::new_seeded(9, 9, 10, seed).with_question_marks(false);
+            game.open((4, 4));
+            for _ in 0..5 {
+                let step = solver.solve_step(&game);
+                let mut fresh = Solver::new();
+                assert_eq!(step, fresh.solve_step(&game));
+                for pos in Solver::positions(&game) {
+                    assert_eq!(
+                        solver.get_mine_chance(pos).value(),
+                        fresh.get_mine_chance(pos).value()
+                    );
+                }
+                match step {
+                    SolverStep::Open(pos) => {
+                        game.open(pos);
+                    }
+                    SolverStep::Flag(pos) => game.flag(pos),
+                    SolverStep::Unflag(pos) => game.clear_mark(pos),
+                    SolverStep::None => break,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_flag_test() {
+        // Two safe fields next to the only mine are flagged, the solver clears them and still wins
+        let mut game = Minesweeper::from_mines(4, 4, &[(3, 3)]).with_question_marks(false);
+        game.flag((3, 2));
+        game.flag((2, 3));
+
+        let mut solver = Solver::new();
+        play(&mut game, &mut solver, 20);
+        assert_eq!(game.game_state, GameState::Win);
+        assert!(!game.is_flagged((3, 2)) && !game.is_flagged((2, 3)));
+    }
+
+    #[test]
+    fn detonated_start_test() {
+        // With lives to spare the first click hit a mine, the corner is not opened again
+        let mut game = Minesweeper::from_mines(1, 2, &[(0, 0)]).with_lives(2);
+        game.open((0, 0));
+        assert!(game.is_detonated((0, 0)) && !game.has_started());
+
+        let mut solver = Solver::new();
+        assert_eq!(solver.solve_step(&game), SolverStep::Open((0, 1)));
+        play(&mut game, &mut solver, 5);
+        assert_eq!(game.game_state, GameState::Win);
+    }
+
+    #[test]
+    fn over_flag_test() {
+        // Flagging more fields than there are mines leaves the chances to the numbers and the real mine count
+        let mines = [(2, 2), (4, 0), (0, 4)];
+        let mut game = Minesweeper::from_mines(5, 5, &mines);
+        let mut flagged = Minesweeper::from_mines(5, 5, &mines);
+        game.open((0, 0));
+        flagged.open((0, 0));
+        for pos in [(4, 0), (4, 1), (0, 4), (1, 4)] {
+            flagged.flag(pos);
+        }
+        assert_eq!(flagged.remaining_mines(), -1);
+
+        let mut solver = Solver::new();
+        let mut flagged_solver = Solver::new();
+        solver.calculate_field(&game);
+        flagged_solver.calculate_field(&flagged);
+        for y in 0..5 {
+            for x in 0..5 {
+                let chance = solver.get_mine_chance((x, y)).value();
+                assert_eq!(flagged_solver.get_mine_chance((x, y)).value(), chance);
+            }
+        }
+        assert_eq!(flagged_solver.get_mine_chance((4, 1)).value(), 0.5);
+    }
+
+    #[test]
+    fn forced_guess_test() {
+        // The right column is a coin flip nothing on the board can ever settle
+        let mut game = board(&["oo*", "oo."], &["01#", "01#"]);
+        let mut solver = Solver::new();
+        solver.calculate_field(&game);
+        assert_eq!(solver.forced_guesses(), [vec![(2, 0), (2, 1)]]);
+        assert_eq!(
+            solver.forced_guess((2, 1)),
+            Some([(2, 0), (2, 1)].as_slice())
+        );
+        assert_eq!(solver.forced_guess((0, 0)), None);
+
+        // Resolving moves the mine within the pair without changing any number
+        assert!(solver.resolve_forced_guess(&mut game, (2, 0)));
+        assert!(!game.is_mined((2, 0)) && game.is_mined((2, 1)));
+        assert_eq!(protocol::format_board(&game), ["01#", "01#"]);
+        assert!(!solver.resolve_forced_guess(&mut game, (2, 0)));
+        game.open((2, 0));
+        assert_eq!(game.game_state, GameState::Win);
+
+        // In two player games the mine is a point to score, so it stays where it is
+        let mut game = Minesweeper::from_mines(3, 2, &[(2, 0)]).with_two_players();
+        game.open((0, 0));
+        assert_eq!(protocol::format_board(&game), ["01#", "01#"]);
+        assert!(!solver.resolve_forced_guess(&mut game, (2, 0)));
+        assert!(solver.forced_guess((2, 0)).is_some());
+        assert!(game.is_mined((2, 0)));
+        game.open((2, 0));
+        assert_eq!(game.scores()[game.current_player()], 1);
+
+        // Fields further on can still tell the twos apart, so they are not forced
+        let game = board(&["o*..", "o*.."], &["2###", "2###"]);
+        solver.calculate_field(&game);
+        assert!(solver.forced_guesses().is_empty());
+        let game = board(
+            &["o....", ".*...", ".....", "...*.", "....*"],
+            &["1####", "#####", "#####", "#####", "#####"],
+        );
+        solver.calculate_field(&game);
+        assert!(solver.forced_guesses().is_empty());
+    }
+}
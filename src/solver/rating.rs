@@ -0,0 +1,365 @@
+//! Rating how hard a board is to solve, by the hardest reasoning needed to clear it
+//!
+//! The board is played from its start field using the simplest reasoning that makes progress: single
+//! numbers first, then pairs of numbers where one's fields contain the other's, then the full analysis
+//! of the solver with the mine count. Only when none of these finds a move is a guess made, so the
+//! rating is the hardest of these steps and the number of guesses taken.
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Solver, SolverStep};
+use crate::{minesweeper::*, race};
+
+/// Boards tried by [find_board] before giving up, far more than any difficulty needs
+pub const MAX_ATTEMPTS: u64 = 2000;
+
+/// Reasoning needed to find a move, from the simplest to the hardest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    /// A single number has as many closed fields as mines left, or no mines left
+    Trivial,
+    /// The fields of one number contain those of another, e.g. the 1-2 pattern
+    Subset,
+    /// Many numbers at once, or the number of mines left on the board
+    Global,
+    /// No field is certain, so a guess is needed
+    Guess,
+}
+
+impl Display for Technique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Technique::Trivial => f.write_str("trivial"),
+            Technique::Subset => f.write_str("subset patterns"),
+            Technique::Global => f.write_str("global counting"),
+            Technique::Guess => f.write_str("guessing"),
+        }
+    }
+}
+
+/// How hard a board is to solve from its start field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rating {
+    /// Hardest technique needed
+    pub technique: Technique,
+    pub guesses: usize,
+}
+
+impl Display for Rating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.guesses {
+            0 => write!(f, "{}", self.technique),
+            1 => write!(f, "{}, 1 guess", self.technique),
+            guesses => write!(f, "{}, {} guesses", self.technique, guesses),
+        }
+    }
+}
+
+/// Class of boards to generate, all solvable without guessing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogicLevel {
+    /// Solved with single numbers and subset patterns
+    Easy,
+    /// Needs reasoning over many numbers or the mine count at least once
+    Hard,
+}
+
+impl LogicLevel {
+    /// Check if a rating falls in this class
+    pub fn accepts(&self, rating: Rating) -> bool {
+        if rating.guesses > 0 {
+            return false;
+        }
+        match self {
+            LogicLevel::Easy => rating.technique <= Technique::Subset,
+            LogicLevel::Hard => rating.technique == Technique::Global,
+        }
+    }
+}
+
+impl Display for LogicLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicLevel::Easy => f.write_str("Easy logic"),
+            LogicLevel::Hard => f.write_str("Hard logic"),
+        }
+    }
+}
+
+/// A seeded board found by [find_board], created with [Minesweeper::new_seeded]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RatedBoard {
+    pub seed: u64,
+    /// Field to open first, see [race::start_field]
+    pub start: Position,
+    pub rating: Rating,
+}
+
+/// Why no board of the requested class was generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatingError {
+    /// The size and mine count do not make a playable board
+    InvalidBoard(MinesweeperError),
+    /// None of the boards tried fell in the class, e.g. hard logic on a nearly empty board
+    NotFound(LogicLevel),
+    /// The search stopped without an answer, e.g. the thread running it panicked
+    Aborted,
+}
+
+impl Display for RatingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RatingError::InvalidBoard(error) => write!(f, "{}", error),
+            RatingError::NotFound(level) => {
+                write!(f, "No {} board in {} attempts", level, MAX_ATTEMPTS)
+            }
+            RatingError::Aborted => f.write_str("Board generation stopped unexpectedly"),
+        }
+    }
+}
+
+impl std::error::Error for RatingError {}
+
+impl From<MinesweeperError> for RatingError {
+    fn from(error: MinesweeperError) -> Self {
+        RatingError::InvalidBoard(error)
+    }
+}
+
+/// Rate a board, opening the given start field first
+///
+/// The board is played on a copy with a life for every mine, so a wrong guess shows the mine and play
+/// goes on
+pub fn rate(game: &Minesweeper, start: Position) -> Rating {
+    let mines = game.mine_positions();
+    let mut game = Minesweeper::from_mines(game.width, game.height, &mines)
+        .with_lives(u8::try_from(mines.len() + 1).unwrap_or(u8::MAX))
+        .with_question_marks(false);
+    let mut solver = Solver::new();
+    let mut rating = Rating {
+        technique: Technique::Trivial,
+        guesses: 0,
+    };
+
+    game.open(start);
+    while game.game_state == GameState::InProgress {
+        let (technique, safe, mined) = find_moves(&game, &mut solver);
+        rating.technique = rating.technique.max(technique);
+
+        if technique == Technique::Guess {
+            match solver.solve_step(&game) {
+                SolverStep::Open(pos) => {
+                    rating.guesses += 1;
+                    game.open(pos);
+                }
+                step => {
+                    log::warn!(
+                        "Rating stopped, the solver gave '{}' instead of a guess",
+                        step
+                    );
+                    break;
+                }
+            }
+            continue;
+        }
+
+        // Mines are only flagged once certain, so the flags can be trusted from here on
+        for pos in mined {
+            game.flag(pos);
+        }
+        for pos in safe {
+            game.open(pos);
+        }
+    }
+
+    return rating;
+}
+
+/// Find certain moves with the simplest technique that gives any, as safe and mined fields
+fn find_moves(
+    game: &Minesweeper,
+    solver: &mut Solver,
+) -> (Technique, Vec<Position>, Vec<Position>) {
+    let constraints = constraints(game);
+    let mut safe = Vec::new();
+    let mut mined = Vec::new();
+
+    for (fields, mines) in &constraints {
+        if *mines == 0 {
+            safe.extend(fields);
+        } else if *mines == fields.len() {
+            mined.extend(fields);
+        }
+    }
+    if !safe.is_empty() || !mined.is_empty() {
+        return (Technique::Trivial, dedup(safe), dedup(mined));
+    }
+
+    for (small, small_mines) in &constraints {
+        for (large, large_mines) in &constraints {
+            if small.len() >= large.len() || !small.iter().all(|pos| large.contains(pos)) {
+                continue;
+            }
+            let rest = large.iter().filter(|pos| !small.contains(pos));
+            if small_mines == large_mines {
+                safe.extend(rest);
+            } else if large_mines - small_mines == large.len() - small.len() {
+                mined.extend(rest);
+            }
+        }
+    }
+    if !safe.is_empty() || !mined.is_empty() {
+        return (Technique::Subset, dedup(safe), dedup(mined));
+    }
+
+    solver.calculate_field(game);
+    for y in 0..game.height {
+        for x in 0..game.width {
+            let pos = (x, y);
+            if !is_unknown(game, pos) {
+                continue;
+            }
+            let chance = solver.get_mine_chance(pos).value();
+            if chance <= 0.0 {
+                safe.push(pos);
+            } else if chance >= 1.0 {
+                mined.push(pos);
+            }
+        }
+    }
+    if !safe.is_empty() || !mined.is_empty() {
+        return (Technique::Global, safe, mined);
+    }
+    return (Technique::Guess, safe, mined);
+}
+
+/// Get the unknown fields around every open number, with the mines among them
+fn constraints(game: &Minesweeper) -> Vec<(Vec<Position>, usize)> {
+    let mut constraints = Vec::new();
+    for y in 0..game.height {
+        for x in 0..game.width {
+            let pos = (x, y);
+            if !game.is_open(pos) || game.is_mined(pos) {
+                continue;
+            }
+            let fields: Vec<Position> = game
+                .neighboring_fields_iter(pos)
+                .filter(|&neighbor| is_unknown(game, neighbor))
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            let known = game
+                .neighboring_fields_iter(pos)
+                .filter(|&neighbor| game.is_flagged(neighbor) || game.is_detonated(neighbor))
+                .count();
+            constraints.push((fields, usize::from(game.neighboring_mines(pos)) - known));
+        }
+    }
+    return constraints;
+}
+
+/// Check if a field is closed and not known to be a mine
+fn is_unknown(game: &Minesweeper, pos: Position) -> bool {
+    return !game.is_open(pos) && !game.is_detonated(pos) && !game.is_flagged(pos);
+}
+
+fn dedup(mut fields: Vec<Position>) -> Vec<Position> {
+    fields.sort();
+    fields.dedup();
+    return fields;
+}
+
+/// Generate seeded boards from the given seed until one falls in the given class
+///
+/// Rating a board plays it through, so this can take a while on big boards and is best run away from
+/// the user interface. Gives up after [MAX_ATTEMPTS] boards rather than return a board of another class
+pub fn find_board(
+    width: u16,
+    height: u16,
+    mines: usize,
+    level: LogicLevel,
+    seed: u64,
+) -> Result<RatedBoard, RatingError> {
+    for attempt in 0..MAX_ATTEMPTS {
+        let seed = seed.wrapping_add(attempt);
+        let game = Minesweeper::try_new_seeded(width, height, mines, seed)?;
+        let start = race::start_field(&game).expect("Generated boards have a safe field");
+        let board = RatedBoard {
+            seed,
+            start,
+            rating: rate(&game, start),
+        };
+
+        if level.accepts(board.rating) {
+            log::debug!("Found a {} board after {} attempts", level, attempt + 1);
+            return Ok(board);
+        }
+    }
+
+    return Err(RatingError::NotFound(level));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_test() {
+        // A single mine in the corner is found from the number next to it
+        let game = Minesweeper::from_mines(4, 4, &[(3, 3)]);
+        let rating = rate(&game, (0, 0));
+        assert_eq!(rating.technique, Technique::Trivial);
+        assert_eq!(rating.guesses, 0);
+
+        // The 1-2-1 along the wall needs the ones inside the two
+        let game = Minesweeper::from_mines(3, 3, &[(0, 2), (2, 2)]);
+        let rating = rate(&game, (0, 0));
+        assert_eq!(rating.technique, Technique::Subset);
+
+        // Nothing tells the two fields of the right column apart
+        let game = Minesweeper::from_mines(3, 2, &[(2, 0)]);
+        let rating = rate(&game, (0, 0));
+        assert_eq!(
+            rating,
+            Rating {
+                technique: Technique::Guess,
+                guesses: 1
+            }
+        );
+        assert_eq!(rating.to_string(), "guessing, 1 guess");
+    }
+
+    #[test]
+    fn find_board_test() {
+        for level in [LogicLevel::Easy, LogicLevel::Hard] {
+            let board = find_board(9, 9, 10, level, 3).unwrap();
+            assert!(
+                level.accepts(board.rating),
+                "{} rated {}",
+                level,
+                board.rating
+            );
+
+            // The seed gives back the same board
+            let game = Minesweeper::new_seeded(9, 9, 10, board.seed);
+            assert_eq!(rate(&game, board.start), board.rating);
+        }
+
+        // A single mine is always found from the numbers, so there is never a hard logic board
+        assert_eq!(
+            find_board(4, 4, 1, LogicLevel::Hard, 0),
+            Err(RatingError::NotFound(LogicLevel::Hard))
+        );
+        assert_eq!(
+            find_board(2, 2, 4, LogicLevel::Easy, 0),
+            Err(RatingError::InvalidBoard(MinesweeperError::TooManyMines {
+                mines: 4,
+                fields: 4
+            }))
+        );
+    }
+}
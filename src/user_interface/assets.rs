@@ -23,7 +23,6 @@ pub struct MinesweeperAssets {
     pub mine_false: image::Handle,
     pub mine_detonated: image::Handle,
     pub question_closed: image::Handle,
-    pub question_open: image::Handle,
 
     //#endregion
 
@@ -101,9 +100,6 @@ impl Default for MinesweeperAssets {
             question_closed: image::Handle::from_bytes(
                 include_bytes!("../../resources/images/field/question_closed.png").as_slice(),
             ),
-            question_open: image::Handle::from_bytes(
-                include_bytes!("../../resources/images/field/question_open.png").as_slice(),
-            ),
             //#endregion
 
             //#region Score indicators
@@ -165,3 +161,80 @@ impl Default for MinesweeperAssets {
         }
     }
 }
+
+impl MinesweeperAssets {
+    /// Names of every tile, as used by skin manifests
+    pub const TILE_NAMES: [&'static str; 32] = [
+        "field0",
+        "field1",
+        "field2",
+        "field3",
+        "field4",
+        "field5",
+        "field6",
+        "field7",
+        "field8",
+        "closed",
+        "flag",
+        "mine",
+        "mine_false",
+        "mine_detonated",
+        "question_closed",
+        "score0",
+        "score1",
+        "score2",
+        "score3",
+        "score4",
+        "score5",
+        "score6",
+        "score7",
+        "score8",
+        "score9",
+        "score_empty",
+        "score_dash",
+        "face",
+        "face_pressed",
+        "face_lose",
+        "face_win",
+        "face_open",
+    ];
+
+    /// Get the tile with the given name
+    pub fn tile_mut(&mut self, name: &str) -> Option<&mut image::Handle> {
+        match name {
+            "field0" => Some(&mut self.field0),
+            "field1" => Some(&mut self.field1),
+            "field2" => Some(&mut self.field2),
+            "field3" => Some(&mut self.field3),
+            "field4" => Some(&mut self.field4),
+            "field5" => Some(&mut self.field5),
+            "field6" => Some(&mut self.field6),
+            "field7" => Some(&mut self.field7),
+            "field8" => Some(&mut self.field8),
+            "closed" => Some(&mut self.closed),
+            "flag" => Some(&mut self.flag),
+            "mine" => Some(&mut self.mine),
+            "mine_false" => Some(&mut self.mine_false),
+            "mine_detonated" => Some(&mut self.mine_detonated),
+            "question_closed" => Some(&mut self.question_closed),
+            "score0" => Some(&mut self.score0),
+            "score1" => Some(&mut self.score1),
+            "score2" => Some(&mut self.score2),
+            "score3" => Some(&mut self.score3),
+            "score4" => Some(&mut self.score4),
+            "score5" => Some(&mut self.score5),
+            "score6" => Some(&mut self.score6),
+            "score7" => Some(&mut self.score7),
+            "score8" => Some(&mut self.score8),
+            "score9" => Some(&mut self.score9),
+            "score_empty" => Some(&mut self.score_empty),
+            "score_dash" => Some(&mut self.score_dash),
+            "face" => Some(&mut self.face),
+            "face_pressed" => Some(&mut self.face_pressed),
+            "face_lose" => Some(&mut self.face_lose),
+            "face_win" => Some(&mut self.face_win),
+            "face_open" => Some(&mut self.face_open),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,497 @@
+//! Daily challenge, everyone gets the same board for each difficulty on a given day
+//!
+//! Days follow UTC so players in different time zones share a board.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::GameDifficulty;
+use crate::{minesweeper::*, race};
+
+/// Calendar date in the proleptic Gregorian calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    /// Get the current date in UTC
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        return Date::from_days((seconds / 86400) as i64);
+    }
+
+    /// Get the date the given number of days after 1970-01-01
+    pub fn from_days(days: i64) -> Date {
+        // Count from 0000-03-01 so leap days fall at the end of each year
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        return Date {
+            year: year as i32,
+            month: month as u8,
+            day: day as u8,
+        };
+    }
+
+    /// Get the number of days since 1970-01-01
+    pub fn days(&self) -> i64 {
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        return era * 146097 + day_of_era - 719468;
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl FromStr for Date {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid date '{}', expected YYYY-MM-DD", s);
+        let mut parts = s.splitn(3, '-');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let (year, month, day) = (next()?, next()?, next()?);
+        let date = Date {
+            year: year.parse().map_err(|_| invalid())?,
+            month: month.parse().map_err(|_| invalid())?,
+            day: day.parse().map_err(|_| invalid())?,
+        };
+
+        // Reject days that do not exist, such as the 30th of February
+        if Date::from_days(date.days()) != date {
+            return Err(invalid());
+        }
+        return Ok(date);
+    }
+}
+
+impl TryFrom<String> for Date {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        return value.parse();
+    }
+}
+
+impl From<Date> for String {
+    fn from(date: Date) -> Self {
+        return date.to_string();
+    }
+}
+
+/// Get the seed of the board for a date and difficulty
+pub fn seed(date: Date, difficulty: GameDifficulty) -> u64 {
+    let difficulty = match difficulty {
+        GameDifficulty::Easy => 0,
+        GameDifficulty::Medium => 1,
+        GameDifficulty::Hard => 2,
+    };
+    return (date.days() as u64) * 3 + difficulty;
+}
+
+/// Create the board for a date and difficulty, with the same start field opened for everyone
+pub fn create_game(date: Date, difficulty: GameDifficulty) -> Minesweeper {
+    let (width, height, mines) = difficulty.board();
+    let mut game = Minesweeper::new_seeded(width, height, mines, seed(date, difficulty));
+    if let Some(start) = race::start_field(&game) {
+        game.open(start);
+    }
+    return game;
+}
+
+/// Outcome of a daily challenge
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub date: Date,
+    pub difficulty: GameDifficulty,
+    pub state: GameState,
+    /// Time taken in milliseconds
+    pub millis: u64,
+    pub three_bv: usize,
+    /// Emoji grid showing when each part of the board was cleared, see [emoji_grid]
+    pub grid: Vec<String>,
+}
+
+impl DailyResult {
+    /// 3BV cleared per second, only meaningful for wins
+    pub fn three_bv_per_second(&self) -> f64 {
+        return self.three_bv as f64 / (self.millis.max(1) as f64 / 1000.0);
+    }
+
+    /// Text summary to share with other players, without giving away the board
+    pub fn share_text(&self) -> String {
+        let outcome = match self.state {
+            GameState::Win => format!(
+                "Cleared in {:.1}s, 3BV {} ({:.2} 3BV/s)",
+                self.millis as f64 / 1000.0,
+                self.three_bv,
+                self.three_bv_per_second()
+            ),
+            _ => format!(
+                "Lost after {:.1}s, 3BV {}",
+                self.millis as f64 / 1000.0,
+                self.three_bv
+            ),
+        };
+        return format!(
+            "Minesweeper daily {} ({:?})\n{}\n{}",
+            self.date,
+            self.difficulty,
+            outcome,
+            self.grid.join("\n")
+        );
+    }
+}
+
+/// Size of the square of fields shown by each emoji
+const GRID_BLOCK_SIZE: u16 = 3;
+
+/// Summarise when each block of the board was cleared, from green (first quarter of the game)
+/// through yellow and orange to red (last quarter), or black if it was never cleared
+///
+/// Blocks are several fields wide, so the grid does not reveal where the mines are.
+pub fn emoji_grid(
+    game: &Minesweeper,
+    opened_at: &HashMap<Position, Duration>,
+    total: Duration,
+) -> Vec<String> {
+    const QUARTERS: [char; 4] = ['🟩', '🟨', '🟧', '🟥'];
+
+    let blocks = |size: u16| size.div_ceil(GRID_BLOCK_SIZE);
+    return (0..blocks(game.height))
+        .map(|block_y| {
+            (0..blocks(game.width))
+                .map(|block_x| {
+                    let fields = (0..GRID_BLOCK_SIZE).flat_map(|dy| {
+                        (0..GRID_BLOCK_SIZE).map(move |dx| {
+                            (
+                                block_x * GRID_BLOCK_SIZE + dx,
+                                block_y * GRID_BLOCK_SIZE + dy,
+                            )
+                        })
+                    });
+
+                    // The block is cleared when its last safe field is opened
+                    let mut cleared = Some(Duration::ZERO);
+                    for pos in fields.filter(|&pos| game.is_in_bounds(pos) && !game.is_mined(pos)) {
+                        cleared = match (cleared, opened_at.get(&pos)) {
+                            (Some(cleared), Some(&opened)) => Some(cleared.max(opened)),
+                            _ => None,
+                        };
+                    }
+
+                    match cleared {
+                        Some(cleared) => {
+                            let quarter = cleared.as_secs_f64() / total.as_secs_f64().max(0.001);
+                            QUARTERS[((quarter * 4.0) as usize).min(3)]
+                        }
+                        None => '⬛',
+                    }
+                })
+                .collect()
+        })
+        .collect();
+}
+
+/// Daily challenge in progress, timing when each field is opened
+#[derive(Debug, Clone)]
+pub struct DailyGame {
+    pub date: Date,
+    pub difficulty: GameDifficulty,
+    /// Set on the player's first move
+    started: Option<Instant>,
+    opened_at: HashMap<Position, Duration>,
+}
+
+impl DailyGame {
+    /// Start timing a daily board, fields already open count as opened at the start
+    pub fn new(date: Date, difficulty: GameDifficulty, game: &Minesweeper) -> DailyGame {
+        let opened_at = (0..game.height)
+            .flat_map(|y| (0..game.width).map(move |x| (x, y)))
+            .filter(|&pos| game.is_open(pos))
+            .map(|pos| (pos, Duration::ZERO))
+            .collect();
+        return DailyGame {
+            date,
+            difficulty,
+            started: None,
+            opened_at,
+        };
+    }
+
+    /// Time taken since the player's first move
+    pub fn elapsed(&self) -> Duration {
+        return self
+            .started
+            .map_or(Duration::ZERO, |started| started.elapsed());
+    }
+
+    /// Note the time of the fields opened by the given events
+    pub fn record(&mut self, events: &[GameEvent]) {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let elapsed = started.elapsed();
+        for event in events {
+            let opened = match event {
+                GameEvent::Opened(pos, _) => std::slice::from_ref(pos),
+                GameEvent::Cascade(positions) => positions.as_slice(),
+                _ => continue,
+            };
+            for &pos in opened {
+                self.opened_at.entry(pos).or_insert(elapsed);
+            }
+        }
+    }
+
+    /// Get the result of the finished game
+    pub fn result(&self, game: &Minesweeper) -> DailyResult {
+        let elapsed = self.elapsed();
+        return DailyResult {
+            date: self.date,
+            difficulty: self.difficulty,
+            state: game.game_state,
+            millis: elapsed.as_millis() as u64,
+            three_bv: game.three_bv(),
+            grid: emoji_grid(game, &self.opened_at, elapsed),
+        };
+    }
+}
+
+/// Results of every daily challenge played, persisted between launches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyHistory {
+    pub results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+    /// Location of the results file in the user's data directory
+    pub fn path() -> Option<PathBuf> {
+        return dirs::data_dir().map(|dir| dir.join("minesweeper").join("daily.toml"));
+    }
+
+    /// Load the results file, starting a new history if it is missing or invalid
+    pub fn load() -> DailyHistory {
+        let Some(path) = Self::path() else {
+            return DailyHistory::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return DailyHistory::default();
+        };
+
+        match toml::from_str(&contents) {
+            Ok(history) => history,
+            Err(error) => {
+                log::warn!(
+                    "Invalid daily results file '{}', starting a new history: {}",
+                    path.display(),
+                    error
+                );
+                DailyHistory::default()
+            }
+        }
+    }
+
+    /// Write the results file, logging any failure
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("No data directory, daily results will not be saved");
+            return;
+        };
+
+        let result = toml::to_string(self)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                fs::write(&path, contents).map_err(|error| error.to_string())
+            });
+
+        match result {
+            Ok(()) => log::debug!("Saved daily results to '{}'", path.display()),
+            Err(error) => log::warn!(
+                "Could not save daily results to '{}': {}",
+                path.display(),
+                error
+            ),
+        }
+    }
+
+    pub fn get(&self, date: Date, difficulty: GameDifficulty) -> Option<&DailyResult> {
+        return self
+            .results
+            .iter()
+            .find(|result| result.date == date && result.difficulty == difficulty);
+    }
+
+    /// Add a result, returning false if that day's board was already played
+    ///
+    /// Only the first attempt counts, replays are not recorded
+    pub fn record(&mut self, result: DailyResult) -> bool {
+        if self.get(result.date, result.difficulty).is_some() {
+            return false;
+        }
+        self.results.push(result);
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_test() {
+        let date = |year, month, day| Date { year, month, day };
+        assert_eq!(Date::from_days(0), date(1970, 1, 1));
+        assert_eq!(date(2000, 3, 1).days(), 11017);
+        assert_eq!(Date::from_days(-1), date(1969, 12, 31));
+        assert_eq!(date(2024, 2, 29).to_string(), "2024-02-29");
+
+        for days in -1000..100_000 {
+            assert_eq!(Date::from_days(days).days(), days);
+        }
+
+        assert_eq!("2026-10-18".parse(), Ok(date(2026, 10, 18)));
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2023-13-01".parse::<Date>().is_err());
+        assert!("yesterday".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn daily_board_test() {
+        // The same day and difficulty always gives the same board
+        let date = Date::from_days(20000);
+        let (first, second) = (
+            create_game(date, GameDifficulty::Medium),
+            create_game(date, GameDifficulty::Medium),
+        );
+        assert!(first.has_started());
+        for y in 0..first.height {
+            for x in 0..first.width {
+                assert_eq!(first.is_mined((x, y)), second.is_mined((x, y)));
+                assert_eq!(first.is_open((x, y)), second.is_open((x, y)));
+            }
+        }
+
+        assert_ne!(
+            seed(date, GameDifficulty::Easy),
+            seed(date, GameDifficulty::Medium)
+        );
+        assert_ne!(
+            seed(date, GameDifficulty::Hard),
+            seed(Date::from_days(20001), GameDifficulty::Easy)
+        );
+    }
+
+    #[test]
+    fn share_text_test() {
+        // Left side cleared at the start, the right only late in the game
+        let mut game = Minesweeper::from_mines(6, 3, &[(3, 0)]);
+        let mut opened_at = HashMap::new();
+        for y in 0..3 {
+            for x in 0..6 {
+                let time = if x < 3 { 1 } else { 9 };
+                opened_at.insert((x, y), Duration::from_secs(time));
+            }
+        }
+        game.open((0, 0));
+        let grid = emoji_grid(&game, &opened_at, Duration::from_secs(10));
+        assert_eq!(grid, vec!["🟩🟥"]);
+
+        // Unopened blocks are black
+        opened_at.remove(&(5, 2));
+        let grid = emoji_grid(&game, &opened_at, Duration::from_secs(10));
+        assert_eq!(grid, vec!["🟩⬛"]);
+
+        let result = DailyResult {
+            date: Date::from_days(20000),
+            difficulty: GameDifficulty::Easy,
+            state: GameState::Win,
+            millis: 10_000,
+            three_bv: 20,
+            grid: vec![String::from("🟩🟥")],
+        };
+        assert_eq!(
+            result.share_text(),
+            "Minesweeper daily 2024-10-04 (Easy)\nCleared in 10.0s, 3BV 20 (2.00 3BV/s)\n🟩🟥"
+        );
+    }
+
+    #[test]
+    fn daily_game_test() {
+        let mut game = Minesweeper::from_mines(5, 3, &[(2, 0), (2, 2)]);
+        game.open((0, 0));
+        game.drain_events();
+        let mut daily = DailyGame::new(Date::from_days(0), GameDifficulty::Easy, &game);
+
+        for pos in [(4, 0), (2, 1)] {
+            game.open(pos);
+            daily.record(&game.drain_events());
+        }
+        let result = daily.result(&game);
+        assert_eq!(result.state, GameState::Win);
+        assert_eq!(result.three_bv, game.three_bv());
+        assert_eq!(result.grid.len(), 1);
+        assert!(!result.grid[0].contains('⬛'));
+    }
+
+    #[test]
+    fn history_test() {
+        let result = DailyResult {
+            date: Date::from_days(20000),
+            difficulty: GameDifficulty::Hard,
+            state: GameState::Loss,
+            millis: 1234,
+            three_bv: 150,
+            grid: vec![String::from("⬛🟩")],
+        };
+        let mut history = DailyHistory::default();
+        assert!(history.record(result.clone()));
+        assert!(!history.record(DailyResult {
+            state: GameState::Win,
+            ..result.clone()
+        }));
+
+        let contents = toml::to_string(&history).unwrap();
+        assert!(contents.contains("date = \"2024-10-04\""));
+        let loaded: DailyHistory = toml::from_str(&contents).unwrap();
+        assert_eq!(loaded.get(result.date, GameDifficulty::Hard), Some(&result));
+        assert_eq!(loaded.get(result.date, GameDifficulty::Easy), None);
+    }
+}
@@ -1,27 +1,44 @@
 pub mod assets;
+pub mod daily;
+pub mod settings;
+pub mod skin;
+pub mod sounds;
 mod styles;
 
 use std::{
+    collections::BTreeMap,
     ops::Deref,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
-    usize,
 };
 
 use crate::{
+    analysis::{self, GameRecord, Review, Verdict},
     minesweeper::*,
-    solver::{Solver, SolverStep},
+    race::{RaceClient, RaceMessage, RaceServer, RaceStart},
+    solver::{
+        rating::{self, LogicLevel, RatedBoard, RatingError},
+        Solver, SolverStep, SolverStrategy,
+    },
 };
-use assets::MinesweeperAssets;
+use daily::{DailyGame, DailyHistory, Date};
 use iced::{
     event,
     keyboard::{self, key::Named, Key},
     mouse::{self, Interaction},
     padding, time,
-    widget::{container, image, tooltip, Column, Container, Image, MouseArea, Row, Text},
+    widget::{
+        container, image, text_input, tooltip, Column, Container, Image, MouseArea, Row,
+        Scrollable, Space, Text,
+    },
     window::{self},
     Alignment, Color, Element, Event, Length, Size, Subscription, Task, Theme,
 };
-use log::info;
+use log::{info, LevelFilter};
+use serde::{Deserialize, Serialize};
+use settings::{Settings, Zoom};
+use skin::Skin;
+use sounds::{Sound, SoundPlayer};
 use styles::ContainerStyles;
 
 #[derive(Debug, Clone)]
@@ -31,6 +48,44 @@ pub enum Message {
     NewGameReleased,
     NewGameOpenMenu,
     NewGameStart(GameDifficulty),
+    NewGameCycleLives,
+    NewGameCyclePlayers,
+    NewGameCycleLogic,
+    ToggleSound,
+    CycleSkin,
+    ZoomIn,
+    ZoomOut,
+    ZoomFit,
+    WindowResized(Size),
+    CycleVolume,
+    OpenPreferences,
+    ClosePreferences,
+    ToggleQuestionMarks,
+    ToggleMarkForcedGuesses,
+    ToggleResolveForcedGuesses,
+    CycleFirstClick,
+    CycleSolverStrategy,
+    CycleZoom,
+    CycleLogLevel,
+    OpenLobby,
+    CloseLobby,
+    RaceAddressChanged(String),
+    HostRace,
+    JoinRace,
+    /// Hosting or joining finished, with the status to show if it worked
+    RaceConnected(String, Handoff<std::io::Result<Race>>),
+    StartRace,
+    LeaveRace,
+    RacePoll,
+    OpenDaily,
+    CloseDaily,
+    /// Review the moves of the finished game
+    OpenAnalysis,
+    CloseAnalysis,
+    DailyStart(GameDifficulty),
+    /// A board of the class set for new games was found, or could not be
+    BoardGenerated(GameDifficulty, Result<RatedBoard, RatingError>),
+    CopyDailyResult(GameDifficulty),
     RestartGame,
     OpenPressed,
     OpenReleased,
@@ -38,52 +93,266 @@ pub enum Message {
     CustomButtonReleased(Box<Option<Self>>),
     Open(Position),
     Flag(Position),
+    /// Clear the flag or question mark on a field
+    Unflag(Position),
     Tick(Instant),
     ShowMineChance,
     HideMineChance,
     SolveStep,
 }
 
+impl Message {
+    /// Check if handling the message can change the board, e.g. not the timer or menus
+    ///
+    /// Race polls only change the board when a race starts, which updates the chances itself
+    fn changes_board(&self) -> bool {
+        return !matches!(
+            self,
+            Message::Ignore
+                | Message::Tick(_)
+                | Message::RacePoll
+                | Message::RaceConnected(_, _)
+                | Message::WindowResized(_)
+                | Message::ZoomIn
+                | Message::ZoomOut
+                | Message::ZoomFit
+                | Message::CycleZoom
+                | Message::ToggleSound
+                | Message::CycleVolume
+                | Message::CycleSkin
+                | Message::CycleLogLevel
+                | Message::NewGamePressed
+                | Message::NewGameReleased
+                | Message::OpenPressed
+                | Message::OpenReleased
+                | Message::RaceAddressChanged(_)
+                | Message::CopyDailyResult(_)
+        );
+    }
+}
+
+/// Value handed from a background task to the update handling it, as messages must be clonable
+#[derive(Debug)]
+pub struct Handoff<T>(Arc<Mutex<Option<T>>>);
+
+impl<T> Handoff<T> {
+    fn new(value: T) -> Self {
+        return Handoff(Arc::new(Mutex::new(Some(value))));
+    }
+
+    /// Take the value, none if a clone of the message already took it
+    fn take(&self) -> Option<T> {
+        return self.0.lock().ok()?.take();
+    }
+}
+
+impl<T> Clone for Handoff<T> {
+    fn clone(&self) -> Self {
+        return Handoff(Arc::clone(&self.0));
+    }
+}
+
+/// State kept for each player of a two player game
+#[derive(Debug, Clone)]
+pub struct PlayerState {
+    pub name: &'static str,
+    pub color: Color,
+    /// Games won since the application started
+    pub wins: usize,
+}
+
+impl PlayerState {
+    fn defaults() -> [PlayerState; 2] {
+        return [
+            PlayerState {
+                name: "Red",
+                color: Color::from_rgb8(200, 30, 30),
+                wins: 0,
+            },
+            PlayerState {
+                name: "Blue",
+                color: Color::from_rgb8(30, 60, 200),
+                wins: 0,
+            },
+        ];
+    }
+}
+
+/// Another player of a LAN race, as last reported by the host
+#[derive(Debug, Clone)]
+pub struct RacePeer {
+    pub name: String,
+    /// Visible board rows, empty until the player first moves
+    pub board: Vec<String>,
+    /// Final state and time taken in milliseconds
+    pub result: Option<(GameState, u64)>,
+}
+
+/// State of a LAN race this player has joined
+#[derive(Debug)]
+pub struct Race {
+    /// Only set for the player hosting the race
+    server: Option<RaceServer>,
+    client: RaceClient,
+    peers: BTreeMap<usize, RacePeer>,
+    /// When the current race started, none while waiting in the lobby
+    started: Option<Instant>,
+    winner: Option<usize>,
+}
+
+impl Race {
+    fn new(server: Option<RaceServer>, client: RaceClient) -> Race {
+        return Race {
+            server,
+            client,
+            peers: BTreeMap::new(),
+            started: None,
+            winner: None,
+        };
+    }
+
+    /// Share this player's progress, including the result when the game just ended
+    fn report(&mut self, game: &Minesweeper, finished: bool) {
+        let Some(started) = self.started else {
+            return;
+        };
+
+        let mut result = self.client.send_board(game);
+        if finished {
+            let millis = started.elapsed().as_millis() as u64;
+            result = result.and_then(|_| self.client.send_finished(game.game_state, millis));
+        }
+        if let Err(error) = result {
+            log::warn!("Could not send race progress: {}", error);
+        }
+    }
+}
+
+/// Name shown to the other players of a race
+fn player_name() -> String {
+    return std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("Player"));
+}
+
 /// Enum representing possible game difficulties
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GameDifficulty {
     Easy,
     Medium,
     Hard,
 }
 
+impl GameDifficulty {
+    /// Width, height and number of mines of the board
+    pub fn board(&self) -> (u16, u16, usize) {
+        match self {
+            GameDifficulty::Easy => (9, 9, 10),
+            GameDifficulty::Medium => (16, 16, 40),
+            GameDifficulty::Hard => (30, 16, 99),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct MinesweeperInterface {
     face_pressed: bool,
     open_pressed: bool,
     show_new_game_menu: bool,
+    show_preferences: bool,
+    show_lobby: bool,
+    show_daily: bool,
+    /// Review of the finished game, shown in the menu while set
+    analysis: Option<Review>,
     game: Minesweeper,
     solver: Solver,
-    assets: MinesweeperAssets,
+    skin: Skin,
+    sounds: SoundPlayer,
     timer: usize,
     timer_enabled: bool,
-    hovered_button_id: Option<String>,
     pressed_button_id: Option<String>,
     show_mine_chance: bool,
     difficulty: GameDifficulty,
+    players: [PlayerState; 2],
+    race: Option<Race>,
+    /// Address typed into the lobby, also used for the port when hosting
+    race_address: String,
+    /// Outcome of the last attempt to host or join a race
+    race_status: Option<String>,
+    /// Whether hosting or joining a race is in progress
+    race_connecting: bool,
+    /// Difficulty and class of the board being generated in the background, if any
+    generating: Option<(GameDifficulty, LogicLevel)>,
+    /// Why the last board could not be generated
+    generation_error: Option<String>,
+    /// Daily challenge being played, if any
+    daily: Option<DailyGame>,
+    daily_history: DailyHistory,
+    settings: Settings,
+    /// Size of the window in unscaled pixels, once known
+    window_size: Option<Size>,
 }
 
 impl Default for MinesweeperInterface {
     fn default() -> Self {
-        Self {
+        return Self::with_settings(Settings::load());
+    }
+}
+
+impl MinesweeperInterface {
+    /// Create the interface with the given settings, starting the generation of the first board if it
+    /// has to be rated
+    pub fn new(settings: Settings) -> (Self, Task<Message>) {
+        let interface = Self::with_settings(settings);
+        if interface.settings.logic.is_none() {
+            return (interface, Task::none());
+        }
+
+        let difficulty = interface.difficulty;
+        return (
+            interface,
+            Task::perform(async {}, move |_| Message::NewGameStart(difficulty)),
+        );
+    }
+
+    fn with_settings(settings: Settings) -> Self {
+        let mut sounds = SoundPlayer::default();
+        sounds.set_volume(settings.volume);
+        sounds.set_muted(!settings.sound);
+
+        let mut interface = Self {
             face_pressed: false,
             open_pressed: false,
             show_new_game_menu: false,
+            show_preferences: false,
+            show_lobby: false,
+            show_daily: false,
+            analysis: None,
             timer: 0,
             timer_enabled: false,
             game: Minesweeper::new(9, 9, 10),
             solver: Solver::new(),
-            assets: Default::default(),
-            hovered_button_id: None,
+            skin: Skin::by_name(&settings.skin),
+            sounds,
             pressed_button_id: None,
             show_mine_chance: false,
-            difficulty: GameDifficulty::Easy,
-        }
+            difficulty: settings.difficulty,
+            players: PlayerState::defaults(),
+            race: None,
+            race_address: format!("127.0.0.1:{}", Self::RACE_PORT),
+            race_status: None,
+            race_connecting: false,
+            generating: None,
+            generation_error: None,
+            daily: None,
+            daily_history: DailyHistory::load(),
+            settings,
+            window_size: None,
+        };
+        interface.game = interface.create_game(interface.difficulty);
+        interface.solver = interface.create_solver();
+        return interface;
     }
 }
 
@@ -91,33 +360,67 @@ impl MinesweeperInterface {
     const EDGE_PADDING: u16 = 10;
     const BORDER_PADDING: u16 = 2;
     const FIELD_SIZE: u16 = 16;
-    const SCALE_FACTOR: u16 = 2;
+    const MAX_LIVES: u8 = 5;
+    const RACE_PORT: u16 = 7879;
+    const MINIMAP_FIELD_SIZE: u16 = 4;
+    const MINIMAP_TEXT_SIZE: u16 = 10;
+    /// Outline of fields that need a guess no other move can avoid
+    const FORCED_GUESS_COLOR: Color = Color::from_rgb(0.88, 0.5, 0.0);
+    const LOG_LEVELS: [LevelFilter; 6] = [
+        LevelFilter::Off,
+        LevelFilter::Error,
+        LevelFilter::Warn,
+        LevelFilter::Info,
+        LevelFilter::Debug,
+        LevelFilter::Trace,
+    ];
 
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         // Layout in a column
-        return Container::new(
-            Column::new()
-                .push(
-                    // Controls row
-                    self.render_wrapper_container(
-                        Row::new()
-                            .push(self.render_remaining_mines_count())
-                            .push(self.render_face())
-                            .push(self.render_timer())
-                            .into(),
-                    ),
-                )
-                // Game board
-                .push(self.render_wrapper_container(self.render_board()))
-                .spacing(Self::EDGE_PADDING)
-                .align_x(Alignment::Center),
-        )
-        .style(ContainerStyles::game_container)
-        .padding(Self::EDGE_PADDING)
-        .into();
+        let mut layout = Column::new()
+            .push(
+                // Controls row
+                self.render_wrapper_container(
+                    Row::new()
+                        .push(self.render_remaining_mines_count())
+                        .push(self.render_face())
+                        .push(self.render_timer())
+                        .into(),
+                ),
+            )
+            // Game board
+            .push(self.render_wrapper_container(self.render_board()))
+            .spacing(Self::EDGE_PADDING)
+            .align_x(Alignment::Center);
+
+        // Progress of the other racers
+        if self.shows_minimaps() {
+            layout = layout.push(self.render_wrapper_container(self.render_minimaps()));
+        }
+
+        return Container::new(layout)
+            .style(ContainerStyles::game_container(self.skin.palette))
+            .padding(Self::EDGE_PADDING)
+            .into();
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
+        let changes_board = message.changes_board();
+        let task = self.handle_message(message);
+        if changes_board {
+            self.update_mine_chances();
+        }
+        return task;
+    }
+
+    /// Keep the shown chances in line with the board, the solver skips the work if no field opened
+    fn update_mine_chances(&mut self) {
+        if self.show_mine_chance || self.settings.mark_forced_guesses {
+            self.solver.calculate_field(&self.game);
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) -> Task<Message> {
         if let Message::Ignore = message {
             return Task::none();
         }
@@ -130,21 +433,27 @@ impl MinesweeperInterface {
             return Task::none();
         }
 
-        let solve_step = self.solver.solve_step(&self.game);
-
         match message {
             // Field open logic
             Message::Open(pos) => {
-                let result = self.game.open(pos);
+                // Shared boards must stay the same for everyone playing them, and in two player games
+                // moving a mine would change the score
+                if self.settings.resolve_forced_guesses
+                    && self.race.is_none()
+                    && self.daily.is_none()
+                    && !self.game.is_two_player()
+                    && self.solver.resolve_forced_guess(&mut self.game, pos)
+                {
+                    log::info!("Resolved the forced guess at '({}, {})'", pos.0, pos.1);
+                }
+                let result = self.game.try_open(pos);
                 self.timer_enabled = true;
                 self.open_pressed = false;
-                if !result.is_none() {
-                    log::info!(
-                        "Opened '({}, {})' with result '{}'",
-                        pos.0,
-                        pos.1,
-                        result.unwrap()
-                    );
+                match result {
+                    Ok(result) => {
+                        log::info!("Opened '({}, {})' with result '{}'", pos.0, pos.1, result)
+                    }
+                    Err(error) => log::debug!("Could not open '({}, {})': {}", pos.0, pos.1, error),
                 }
             }
             Message::OpenPressed => {
@@ -156,11 +465,20 @@ impl MinesweeperInterface {
 
             // Field flag logic
             Message::Flag(pos) => {
-                self.game.flag(pos);
+                let result = self.game.try_flag(pos);
                 self.timer_enabled = true;
                 self.open_pressed = false;
-                log::info!("Flagged '({}, {})'", pos.0, pos.1);
+                match result {
+                    Ok(()) => log::info!("Flagged '({}, {})'", pos.0, pos.1),
+                    Err(error) => log::debug!("Could not flag '({}, {})': {}", pos.0, pos.1, error),
+                }
             }
+            Message::Unflag(pos) => match self.game.try_clear_mark(pos) {
+                Ok(()) => log::info!("Unflagged '({}, {})'", pos.0, pos.1),
+                Err(error) => {
+                    log::debug!("Could not unflag '({}, {})': {}", pos.0, pos.1, error)
+                }
+            },
 
             // New game logic
             Message::NewGamePressed => {
@@ -171,27 +489,304 @@ impl MinesweeperInterface {
             }
             Message::NewGameOpenMenu => {
                 self.show_new_game_menu = true;
+                self.show_preferences = false;
+                self.show_lobby = false;
+                self.show_daily = false;
+                self.analysis = None;
                 self.face_pressed = false;
                 self.timer_enabled = false;
                 self.timer = 0;
                 log::info!("Showing new game menu")
             }
             Message::NewGameStart(difficulty) => {
-                self.show_new_game_menu = false;
-                self.game = match difficulty {
-                    GameDifficulty::Easy => Minesweeper::new(9, 9, 10),
-                    GameDifficulty::Medium => Minesweeper::new(16, 16, 40),
-                    GameDifficulty::Hard => Minesweeper::new(30, 16, 99),
+                let Some(logic) = self.settings.logic else {
+                    let game = self.create_game(difficulty);
+                    return self.start_game(difficulty, game);
+                };
+
+                // Rating boards plays them through, so the menu waits while it is done elsewhere
+                self.generating = Some((difficulty, logic));
+                self.generation_error = None;
+                self.show_new_game_menu = true;
+                self.show_preferences = false;
+                self.show_lobby = false;
+                self.show_daily = false;
+                self.analysis = None;
+                log::info!("Generating a {} board", logic);
+                let (width, height, mines) = difficulty.board();
+                return Task::perform(generate_board(width, height, mines, logic), move |result| {
+                    Message::BoardGenerated(difficulty, result)
+                });
+            }
+            Message::BoardGenerated(difficulty, result) => {
+                // A daily, race or other difficulty asked for since is left alone
+                let logic = match self.generating {
+                    Some((pending, logic)) if pending == difficulty => logic,
+                    _ => {
+                        log::debug!("Ignoring a board generated for a game no longer wanted");
+                        return Task::none();
+                    }
                 };
+                self.generating = None;
+
+                match result {
+                    Ok(board) => {
+                        log::info!("Generated a {} board rated {}", logic, board.rating);
+                        let game = self.create_rated_game(difficulty, board);
+                        return self.start_game(difficulty, game);
+                    }
+                    Err(error) => {
+                        log::warn!("Could not generate a {} board: {}", logic, error);
+                        self.generation_error = Some(error.to_string());
+                    }
+                }
+            }
+            Message::NewGameCycleLives => {
+                self.settings.lives = self.settings.lives % Self::MAX_LIVES + 1;
+                self.settings.save();
+                log::info!("Lives for new games set to {}", self.settings.lives);
+            }
+            Message::NewGameCyclePlayers => {
+                self.settings.players = self.settings.players % 2 + 1;
+                self.settings.save();
+                log::info!("Players for new games set to {}", self.settings.players);
+            }
+            Message::NewGameCycleLogic => {
+                self.settings.logic = match self.settings.logic {
+                    None => Some(LogicLevel::Easy),
+                    Some(LogicLevel::Easy) => Some(LogicLevel::Hard),
+                    Some(LogicLevel::Hard) => None,
+                };
+                self.settings.save();
+                log::info!(
+                    "Boards for new games: {}",
+                    self.settings
+                        .logic
+                        .map_or(String::from("Any"), |logic| logic.to_string())
+                );
+            }
+            Message::OpenPreferences => {
+                self.show_preferences = true;
+                log::info!("Showing preferences")
+            }
+            Message::ClosePreferences => {
+                self.show_preferences = false;
+            }
+            Message::OpenLobby => {
+                self.show_lobby = true;
+                log::info!("Showing race lobby")
+            }
+            Message::CloseLobby => {
+                self.show_lobby = false;
+            }
+            Message::RaceAddressChanged(address) => {
+                self.race_address = address;
+            }
+            Message::HostRace if !self.race_connecting => {
+                let port = self
+                    .race_address
+                    .rsplit_once(':')
+                    .and_then(|(_, port)| port.parse().ok())
+                    .unwrap_or(Self::RACE_PORT);
+                return self.connect_race(format!("Hosting on port {}", port), move || {
+                    let server = RaceServer::bind(("0.0.0.0", port))?;
+                    let client = RaceClient::connect(("127.0.0.1", port), &player_name())?;
+                    Ok(Race::new(Some(server), client))
+                });
+            }
+            Message::JoinRace if !self.race_connecting => {
+                let address = self.race_address.clone();
+                return self.connect_race(format!("Joined {}", address), move || {
+                    let client = RaceClient::connect(address.as_str(), &player_name())?;
+                    Ok(Race::new(None, client))
+                });
+            }
+            Message::RaceConnected(status, race) => {
+                let Some(race) = race.take() else {
+                    return Task::none();
+                };
+                self.race_connecting = false;
+                self.join_race(race, status);
+            }
+            Message::StartRace => {
+                let (width, height, mines) = self.difficulty.board();
+                let server = self.race.as_ref().and_then(|race| race.server.as_ref());
+                match (server, RaceStart::new(width, height, mines, rand::random())) {
+                    (Some(server), Ok(start)) => server.start(start),
+                    (None, _) => log::warn!("Only the host can start the race"),
+                    (_, Err(error)) => log::warn!("Could not start the race: {}", error),
+                }
+            }
+            Message::LeaveRace => {
+                self.race = None;
+                self.race_status = Some(String::from("Left the race"));
+                log::info!("Left the race");
+            }
+            Message::RacePoll => {
+                return self.poll_race();
+            }
+            Message::OpenDaily => {
+                self.show_daily = true;
+                log::info!("Showing daily challenges")
+            }
+            Message::CloseDaily => {
+                self.show_daily = false;
+            }
+            Message::OpenAnalysis => {
+                let review = analysis::review(&GameRecord::from_game(&self.game));
+                log::info!("Game analysis:\n{}", review);
+                self.analysis = Some(review);
+            }
+            Message::CloseAnalysis => {
+                self.analysis = None;
+            }
+            Message::DailyStart(difficulty) => {
+                let date = Date::today();
+                let mut game = daily::create_game(date, difficulty)
+                    .with_question_marks(self.settings.question_marks);
+                game.drain_events();
+                self.daily = Some(DailyGame::new(date, difficulty, &game));
+                self.race = None;
+                self.generating = None;
+                self.game = game;
                 self.difficulty = difficulty;
+                self.solver = self.create_solver();
+                self.show_new_game_menu = false;
+                self.show_daily = false;
                 self.timer_enabled = false;
                 self.timer = 0;
-                self.solver = Solver::new();
-                log::info!("Starting new game with difficulty {:?}", difficulty);
+                if self.daily_history.get(date, difficulty).is_some() {
+                    log::info!(
+                        "Replaying the {:?} daily for {}, the result will not be recorded",
+                        difficulty,
+                        date
+                    );
+                } else {
+                    log::info!("Starting the {:?} daily for {}", difficulty, date);
+                }
+
+                return self.resize_window();
+            }
+            Message::CopyDailyResult(difficulty) => {
+                if let Some(result) = self.daily_history.get(Date::today(), difficulty) {
+                    log::info!("Copied the {:?} daily result", difficulty);
+                    return iced::clipboard::write(result.share_text());
+                }
+            }
+            Message::ToggleQuestionMarks => {
+                self.settings.question_marks = !self.settings.question_marks;
+                self.game.set_question_marks(self.settings.question_marks);
+                self.settings.save();
+                log::info!("Question marks: {}", self.settings.question_marks);
+            }
+            Message::ToggleMarkForcedGuesses => {
+                self.settings.mark_forced_guesses = !self.settings.mark_forced_guesses;
+                self.settings.save();
+                log::info!("Mark 50/50s: {}", self.settings.mark_forced_guesses);
+            }
+            Message::ToggleResolveForcedGuesses => {
+                self.settings.resolve_forced_guesses = !self.settings.resolve_forced_guesses;
+                self.settings.save();
+                log::info!("Resolve 50/50s: {}", self.settings.resolve_forced_guesses);
+            }
+            Message::CycleFirstClick => {
+                self.settings.first_click = match self.settings.first_click {
+                    FirstClick::Unprotected => FirstClick::Safe,
+                    FirstClick::Safe => FirstClick::Opening,
+                    FirstClick::Opening => FirstClick::Unprotected,
+                };
+                self.settings.save();
+                log::info!(
+                    "First click for new games set to {}",
+                    self.settings.first_click
+                );
+            }
+            Message::CycleSolverStrategy => {
+                self.settings.solver_strategy = match self.settings.solver_strategy {
+                    SolverStrategy::Guess => SolverStrategy::SafestGuess,
+                    SolverStrategy::SafestGuess => SolverStrategy::SafeOnly,
+                    SolverStrategy::SafeOnly => SolverStrategy::Guess,
+                };
+                self.solver = self.create_solver();
+                self.settings.save();
+                log::info!("Solver strategy set to {}", self.settings.solver_strategy);
+            }
+            Message::CycleZoom => {
+                self.settings.zoom = match self.settings.zoom {
+                    Zoom::Scale(scale) if scale < Zoom::MAX_SCALE => Zoom::Scale(scale + 1),
+                    Zoom::Scale(_) => Zoom::FitToWindow,
+                    Zoom::FitToWindow => Zoom::Scale(Zoom::MIN_SCALE),
+                };
+                self.settings.save();
+                log::info!("Zoom set to {:?}", self.settings.zoom);
 
-                // Return re-size task
-                let size = self.calculate_size();
-                return window::get_latest().and_then(move |id| window::resize(id, size));
+                return self.resize_window();
+            }
+            Message::CycleLogLevel => {
+                let next = Self::LOG_LEVELS
+                    .iter()
+                    .position(|level| *level == self.settings.log_level)
+                    .map_or(0, |i| (i + 1) % Self::LOG_LEVELS.len());
+                self.settings.log_level = Self::LOG_LEVELS[next];
+                log::set_max_level(self.settings.log_level);
+                self.settings.save();
+                log::info!("Log level set to {}", self.settings.log_level);
+            }
+            Message::ToggleSound => {
+                self.sounds.set_muted(!self.sounds.is_muted());
+                self.settings.sound = !self.sounds.is_muted();
+                self.settings.save();
+                log::info!("Sound muted: {}", self.sounds.is_muted());
+            }
+            Message::CycleVolume => {
+                // Step the volume up by a quarter, wrapping back round to the lowest step
+                let step = (self.sounds.volume() * 4.0).round() as u8 % 4 + 1;
+                self.sounds.set_volume(f32::from(step) / 4.0);
+                self.sounds.play(Sound::Click);
+                self.settings.volume = self.sounds.volume();
+                self.settings.save();
+                log::info!("Sound volume set to {}", self.sounds.volume());
+            }
+            Message::CycleSkin => {
+                let skins = Skin::available();
+                let next = skins
+                    .iter()
+                    .position(|name| *name == self.skin.name)
+                    .map_or(0, |i| (i + 1) % skins.len());
+                self.skin = Skin::by_name(&skins[next]);
+                self.settings.skin = self.skin.name.clone();
+                self.settings.save();
+                log::info!("Using skin '{}'", self.skin.name);
+            }
+            Message::ZoomIn | Message::ZoomOut | Message::ZoomFit => {
+                // Step from the nearest whole scale when leaving fit to window
+                let scale = self.scale_factor();
+                self.settings.zoom = match (message, self.settings.zoom) {
+                    (Message::ZoomFit, _) => Zoom::FitToWindow,
+                    (Message::ZoomIn, Zoom::Scale(scale)) => Zoom::Scale(scale + 1),
+                    (Message::ZoomIn, Zoom::FitToWindow) => Zoom::Scale(scale.floor() as u16 + 1),
+                    (_, Zoom::Scale(scale)) => Zoom::Scale(scale.saturating_sub(1)),
+                    (_, Zoom::FitToWindow) => Zoom::Scale(scale.ceil() as u16 - 1),
+                };
+                if let Zoom::Scale(scale) = self.settings.zoom {
+                    self.settings.zoom = Zoom::Scale(scale.clamp(Zoom::MIN_SCALE, Zoom::MAX_SCALE));
+                }
+                self.settings.save();
+                log::info!("Zoom set to {:?}", self.settings.zoom);
+
+                return self.resize_window();
+            }
+            Message::WindowResized(size) => {
+                // Resize events are reported in scaled pixels
+                let scale = self.scale_factor() as f32;
+                self.window_size = Some(Size::new(size.width * scale, size.height * scale));
+            }
+            Message::RestartGame if self.race.is_some() => {
+                log::info!("Races cannot be restarted, leave the race from the lobby first");
+            }
+            Message::RestartGame if self.daily.is_some() => {
+                let difficulty = self.difficulty;
+                return Task::perform(async {}, move |_| Message::DailyStart(difficulty));
             }
             Message::RestartGame => {
                 log::info!("Restarting game");
@@ -218,22 +813,24 @@ impl MinesweeperInterface {
             }
 
             // Game solver related
-            Message::ShowMineChance => {
-                if !self.show_mine_chance {
-                    self.show_mine_chance = true;
-                    log::info!("Showing solver mine chance");
-                }
+            Message::ShowMineChance if !self.show_mine_chance => {
+                self.show_mine_chance = true;
+                log::info!("Showing solver mine chance");
             }
-            Message::HideMineChance => {
-                if self.show_mine_chance {
-                    self.show_mine_chance = false;
-                    log::info!("Hiding solver mine chance");
-                }
+            Message::HideMineChance if self.show_mine_chance => {
+                self.show_mine_chance = false;
+                log::info!("Hiding solver mine chance");
+            }
+            // Shared boards are compared between players, so they must be solved without help
+            Message::SolveStep if self.race.is_some() || self.daily.is_some() => {
+                log::info!("The solver is disabled during races and daily challenges");
             }
             Message::SolveStep => {
+                let solve_step = self.solver.solve_step(&self.game);
                 info!("Running solver step '{}'", solve_step);
                 let message = match solve_step {
                     SolverStep::Flag(pos) => Message::Flag(pos),
+                    SolverStep::Unflag(pos) => Message::Unflag(pos),
                     SolverStep::Open(pos) => Message::Open(pos),
                     SolverStep::None => Message::Ignore,
                 };
@@ -245,105 +842,409 @@ impl MinesweeperInterface {
             _ => {}
         }
 
+        self.handle_game_events();
         return Task::none();
     }
 
+    /// Host or join a race on another thread, as the host may be slow to answer or never answer at all
+    fn connect_race(
+        &mut self,
+        status: String,
+        connect: impl FnOnce() -> std::io::Result<Race> + Send + 'static,
+    ) -> Task<Message> {
+        log::info!("Connecting to race: {}", status);
+        self.race_connecting = true;
+        self.race_status = Some(String::from("Connecting…"));
+        return Task::perform(in_background(connect), move |race| {
+            let race = race
+                .unwrap_or_else(|| Err(std::io::Error::other("Connecting stopped unexpectedly")));
+            Message::RaceConnected(status.clone(), Handoff::new(race))
+        });
+    }
+
+    /// Connect to a race, showing the outcome in the lobby
+    fn join_race(&mut self, race: std::io::Result<Race>, status: String) {
+        match race {
+            Ok(race) => {
+                log::info!("{}", status);
+                self.race = Some(race);
+                self.race_status = Some(status);
+            }
+            Err(error) => {
+                log::warn!("Could not join race: {}", error);
+                self.race_status = Some(format!("Failed: {}", error));
+            }
+        }
+    }
+
+    /// Handle the messages received from the race host since the last poll
+    fn poll_race(&mut self) -> Task<Message> {
+        let messages = match &mut self.race {
+            Some(race) => race.client.poll(),
+            None => return Task::none(),
+        };
+
+        let mut task = Task::none();
+        for message in messages {
+            log::debug!("Race message '{}'", message);
+            let Some(race) = &mut self.race else {
+                break;
+            };
+            match message {
+                RaceMessage::Welcome(_) => {}
+                RaceMessage::Joined(id, name) => {
+                    log::info!("{} joined the race", name);
+                    race.peers.insert(
+                        id,
+                        RacePeer {
+                            name,
+                            board: Vec::new(),
+                            result: None,
+                        },
+                    );
+                }
+                RaceMessage::Left(id) => {
+                    if let Some(peer) = race.peers.remove(&id) {
+                        log::info!("{} left the race", peer.name);
+                    }
+                }
+                RaceMessage::Board(id, board) => {
+                    if let Some(peer) = race.peers.get_mut(&id) {
+                        peer.board = board;
+                    }
+                }
+                RaceMessage::Finished(id, state, millis) => {
+                    if let Some(peer) = race.peers.get_mut(&id) {
+                        peer.result = Some((state, millis));
+                    }
+                }
+                RaceMessage::Winner(id) => {
+                    race.winner = Some(id);
+                    if let Some(peer) = race.peers.get(&id) {
+                        log::info!("{} won the race", peer.name);
+                    }
+                }
+                RaceMessage::Start(start) => {
+                    let game = match start.create_game() {
+                        Ok(game) => game,
+                        Err(error) => {
+                            log::warn!("Could not create the race board: {}", error);
+                            continue;
+                        }
+                    };
+                    race.started = Some(Instant::now());
+                    race.winner = None;
+                    for peer in race.peers.values_mut() {
+                        peer.board.clear();
+                        peer.result = None;
+                    }
+
+                    self.game = game.with_question_marks(self.settings.question_marks);
+                    self.solver = self.create_solver();
+                    self.daily = None;
+                    self.generating = None;
+                    self.show_new_game_menu = false;
+                    self.show_lobby = false;
+                    self.timer = 0;
+                    self.timer_enabled = true;
+                    log::info!("Race started on a {}x{} board", start.width, start.height);
+                    // Share the opened start field
+                    self.handle_game_events();
+                    self.update_mine_chances();
+                    task = self.resize_window();
+                }
+            }
+        }
+
+        if let Some(race) = &self.race {
+            if !race.client.is_connected() {
+                log::warn!("Lost connection to the race host");
+                self.race = None;
+                self.race_status = Some(String::from("Disconnected from the host"));
+            }
+        }
+        return task;
+    }
+
+    /// Save the result of the daily challenge that just ended
+    fn finish_daily(&mut self) {
+        let Some(daily) = self.daily.take() else {
+            return;
+        };
+
+        let result = daily.result(&self.game);
+        log::info!("Daily result:\n{}", result.share_text());
+        if self.daily_history.record(result) {
+            self.daily_history.save();
+        }
+    }
+
+    /// Keep the finished game so it can be analysed later, e.g. with the `analyze` binary
+    fn save_record(&self) {
+        let Some(path) = GameRecord::path() else {
+            log::warn!("No data directory, the game will not be saved for analysis");
+            return;
+        };
+
+        match GameRecord::from_game(&self.game).save(&path) {
+            Ok(()) => log::debug!("Saved the game to '{}'", path.display()),
+            Err(error) => log::warn!("Could not save the game to '{}': {}", path.display(), error),
+        }
+    }
+
+    /// Consume the events emitted by the game since the last message
+    fn handle_game_events(&mut self) {
+        let events = self.game.drain_events();
+        if !events.is_empty() {
+            if let Some(race) = &mut self.race {
+                let finished = events
+                    .iter()
+                    .any(|event| matches!(event, GameEvent::Won | GameEvent::Lost));
+                race.report(&self.game, finished);
+            }
+            if let Some(daily) = &mut self.daily {
+                daily.record(&events);
+                if self.game.game_state != GameState::InProgress {
+                    self.finish_daily();
+                }
+            }
+            if events
+                .iter()
+                .any(|event| matches!(event, GameEvent::Won | GameEvent::Lost))
+            {
+                self.save_record();
+            }
+        }
+
+        for event in events {
+            if let Some(sound) = Sound::for_event(&event) {
+                self.sounds.play(sound);
+            }
+            match event {
+                GameEvent::Won if self.game.is_two_player() => {
+                    self.timer_enabled = false;
+                    match self.game.winner() {
+                        Some(winner) => {
+                            self.players[winner].wins += 1;
+                            log::info!("{} won the game", self.players[winner].name);
+                        }
+                        None => log::info!("Game ended in a draw"),
+                    }
+                }
+                GameEvent::Won => {
+                    self.timer_enabled = false;
+                    log::info!("Game won in {} seconds", self.timer);
+                }
+                GameEvent::Lost => {
+                    self.timer_enabled = false;
+                    log::info!("Game lost after {} seconds", self.timer);
+                }
+                GameEvent::MineDetonated(pos) => {
+                    log::info!("Mine detonated at '({}, {})'", pos.0, pos.1);
+                }
+                GameEvent::MineClaimed(pos, player) => {
+                    log::info!(
+                        "{} found the mine at '({}, {})'",
+                        self.players[player].name,
+                        pos.0,
+                        pos.1
+                    );
+                }
+                event => log::debug!("Game event {:?}", event),
+            }
+        }
+    }
+
     pub fn title(&self) -> String {
+        if self.race.is_some() {
+            return String::from("Minesweeper - Race");
+        }
+        if let Some(daily) = &self.daily {
+            return format!("Minesweeper - Daily {}", daily.date);
+        }
+        // Show the games won by each player
+        if self.game.is_two_player() {
+            let [first, second] = &self.players;
+            return format!(
+                "Minesweeper - {} {} : {} {}",
+                first.name, first.wins, second.wins, second.name
+            );
+        }
         String::from("Minesweeper")
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        let _r_key = Key::Character('r');
+        // Check for messages from the race host
+        let race = match self.race {
+            Some(_) => time::every(Duration::from_millis(100)).map(|_| Message::RacePoll),
+            None => Subscription::none(),
+        };
 
         Subscription::batch(vec![
+            race,
             // Timer
             time::every(Duration::from_secs(1)).map(Message::Tick),
-            // Keyboard events
+            // Keyboard and window events
             event::listen().map(|event| match event {
                 Event::Keyboard(keyboard_event) => match keyboard_event {
-                    keyboard::Event::KeyPressed {
-                        key: Key::Named(Named::Alt),
-                        location: _,
-                        modified_key: _,
-                        modifiers: _,
-                        physical_key: _,
-                        text: _,
-                    } => Message::ShowMineChance,
+                    keyboard::Event::KeyPressed { key, .. } => match key.as_ref() {
+                        Key::Named(Named::Alt) => Message::ShowMineChance,
+                        Key::Named(Named::Enter) => Message::SolveStep,
+                        Key::Character("r") => Message::RestartGame,
+                        Key::Character("+") | Key::Character("=") => Message::ZoomIn,
+                        Key::Character("-") => Message::ZoomOut,
+                        Key::Character("0") => Message::ZoomFit,
+                        _ => Message::Ignore,
+                    },
                     keyboard::Event::KeyReleased {
                         key: Key::Named(Named::Alt),
-                        location: _,
-                        modifiers: _,
+                        ..
                     } => Message::HideMineChance,
-                    keyboard::Event::KeyPressed {
-                        key: Key::Named(Named::Enter),
-                        location: _,
-                        modified_key: _,
-                        modifiers: _,
-                        physical_key: _,
-                        text: _,
-                    } => Message::SolveStep,
-                    keyboard::Event::KeyPressed {
-                        key: _r_key,
-                        location: _,
-                        modified_key: _,
-                        modifiers: _,
-                        physical_key: _,
-                        text: _,
-                    } => Message::RestartGame,
                     _ => Message::Ignore,
                 },
+                Event::Window(window::Event::Resized(size)) => Message::WindowResized(size),
                 _ => Message::Ignore,
             }),
         ])
     }
 
     pub fn scale_factor(&self) -> f64 {
-        return Self::SCALE_FACTOR.into();
+        match (self.settings.zoom, self.window_size) {
+            (Zoom::Scale(scale), _) => scale.into(),
+            (Zoom::FitToWindow, Some(window_size)) => {
+                let size = self.unscaled_size();
+                let scale = (window_size.width / size.width).min(window_size.height / size.height);
+                return scale.max(0.25).into();
+            }
+            // Window size is not known until the first resize event
+            (Zoom::FitToWindow, None) => Zoom::default().scale().into(),
+        }
     }
 
     pub fn calculate_size(&self) -> Size {
+        let size = self.unscaled_size();
+        let scale = self.scale_factor() as f32;
+
+        return Size::new(size.width * scale, size.height * scale);
+    }
+
+    /// Size of the interface before zoom is applied
+    fn unscaled_size(&self) -> Size {
         let width = (self.game.width * Self::FIELD_SIZE)
             + (Self::EDGE_PADDING * 2)
             + (Self::BORDER_PADDING * 2);
 
-        let height = ((self.game.height + 1) * Self::FIELD_SIZE)
+        let mut height = ((self.game.height + 1) * Self::FIELD_SIZE)
             + (Self::EDGE_PADDING * 4)
             + (Self::BORDER_PADDING * 3);
 
-        return Size::new(
-            (width * Self::SCALE_FACTOR).into(),
-            (height * Self::SCALE_FACTOR).into(),
+        if self.shows_minimaps() {
+            height += (self.game.height * Self::MINIMAP_FIELD_SIZE)
+                + (Self::MINIMAP_TEXT_SIZE * 2)
+                + Self::EDGE_PADDING
+                + (Self::BORDER_PADDING * 6);
+        }
+
+        return Size::new(width.into(), height.into());
+    }
+
+    /// Resize the window to fit the board at a fixed zoom, fit to window leaves the window as it is
+    fn resize_window(&self) -> Task<Message> {
+        if self.settings.zoom == Zoom::FitToWindow {
+            return Task::none();
+        }
+
+        let size = self.calculate_size();
+        return window::get_latest().and_then(move |id| window::resize(id, size));
+    }
+
+    /// Create a game with random mines for the given difficulty using the current settings
+    fn create_game(&self, difficulty: GameDifficulty) -> Minesweeper {
+        let (width, height, mines) = difficulty.board();
+        return self
+            .with_game_settings(Minesweeper::new(width, height, mines))
+            .with_first_click(self.settings.first_click);
+    }
+
+    /// Create the game of a rated board, opened at the field it was rated from
+    ///
+    /// The start field is opened straight away, so the first click needs no protection
+    fn create_rated_game(&self, difficulty: GameDifficulty, board: RatedBoard) -> Minesweeper {
+        let (width, height, mines) = difficulty.board();
+        let mut game =
+            self.with_game_settings(Minesweeper::new_seeded(width, height, mines, board.seed));
+        game.open(board.start);
+        game.drain_events();
+        return game;
+    }
+
+    /// Replace the current game with a new one of the given difficulty
+    fn start_game(&mut self, difficulty: GameDifficulty, game: Minesweeper) -> Task<Message> {
+        if self.race.take().is_some() {
+            log::info!("Left the race to start a new game");
+        }
+        self.daily = None;
+        self.generating = None;
+        self.generation_error = None;
+        self.show_new_game_menu = false;
+        self.show_preferences = false;
+        self.show_lobby = false;
+        self.show_daily = false;
+        self.analysis = None;
+        self.game = game;
+        self.difficulty = difficulty;
+        self.timer_enabled = false;
+        self.timer = 0;
+        self.solver = self.create_solver();
+        if self.settings.difficulty != difficulty {
+            self.settings.difficulty = difficulty;
+            self.settings.save();
+        }
+        log::info!(
+            "Starting new game with difficulty {:?} and {} lives",
+            difficulty,
+            self.settings.lives
         );
+
+        return self.resize_window();
+    }
+
+    /// Apply the lives, question marks and players for new games
+    fn with_game_settings(&self, game: Minesweeper) -> Minesweeper {
+        let game = game
+            .with_lives(self.settings.lives)
+            .with_question_marks(self.settings.question_marks);
+
+        if self.settings.players == 2 {
+            return game.with_two_players();
+        }
+        return game;
     }
 
-    fn render_board(&self) -> Element<Message> {
+    fn create_solver(&self) -> Solver {
+        return Solver::new().with_strategy(self.settings.solver_strategy);
+    }
+
+    fn render_board(&self) -> Element<'_, Message> {
         // Build the game board
         let mut board = Column::new().height(Length::Fill).width(Length::Fill);
 
         if self.show_new_game_menu {
+            let menu = if self.show_preferences {
+                self.render_preferences()
+            } else if self.show_lobby {
+                self.render_lobby()
+            } else if self.show_daily {
+                self.render_daily()
+            } else if let Some(review) = &self.analysis {
+                self.render_analysis(review)
+            } else {
+                self.render_new_game_menu()
+            };
             board = board
-                .push(
-                    Column::new()
-                        .push(self.render_button(
-                            String::from("easy-button"),
-                            String::from("Easy"),
-                            Message::NewGameStart(GameDifficulty::Easy),
-                        ))
-                        .push(self.render_button(
-                            String::from("medium-button"),
-                            String::from("Medium"),
-                            Message::NewGameStart(GameDifficulty::Medium),
-                        ))
-                        .push(self.render_button(
-                            String::from("hard-button"),
-                            String::from("Hard"),
-                            Message::NewGameStart(GameDifficulty::Hard),
-                        ))
-                        .spacing(5)
-                        .max_width(200),
-                )
+                .push(Scrollable::new(menu.spacing(5).max_width(200)))
                 .align_x(Alignment::Center)
-                .padding(padding::all(25))
+                .padding(padding::all(15))
         } else {
             for y in 0..self.game.height {
                 let mut row = Row::new();
@@ -358,21 +1259,406 @@ impl MinesweeperInterface {
         return board.into();
     }
 
-    fn render_button(&self, id: String, text: String, message: Message) -> Element<Message> {
+    fn render_new_game_menu(&self) -> Column<'_, Message> {
+        let text = |content: String| Text::new(content).size(11).color(self.skin.palette.text);
+        if let Some((_, logic)) = self.generating {
+            return Column::new().push(text(format!("Generating {} board…", logic)));
+        }
+
+        let mut menu = Column::new();
+        if let Some(error) = &self.generation_error {
+            menu = menu.push(text(error.clone()));
+        }
+        if self.game.game_state != GameState::InProgress && !self.game.moves().is_empty() {
+            menu = menu.push(self.render_button(
+                String::from("analyze-button"),
+                String::from("Analyze last game"),
+                Message::OpenAnalysis,
+            ));
+        }
+
+        return menu
+            .push(self.render_button(
+                String::from("easy-button"),
+                String::from("Easy"),
+                Message::NewGameStart(GameDifficulty::Easy),
+            ))
+            .push(self.render_button(
+                String::from("medium-button"),
+                String::from("Medium"),
+                Message::NewGameStart(GameDifficulty::Medium),
+            ))
+            .push(self.render_button(
+                String::from("hard-button"),
+                String::from("Hard"),
+                Message::NewGameStart(GameDifficulty::Hard),
+            ))
+            .push(self.render_button(
+                String::from("lives-button"),
+                format!("Lives: {}", self.settings.lives),
+                Message::NewGameCycleLives,
+            ))
+            .push(self.render_button(
+                String::from("players-button"),
+                format!("Players: {}", self.settings.players),
+                Message::NewGameCyclePlayers,
+            ))
+            .push(self.render_button(
+                String::from("logic-button"),
+                format!(
+                    "Board: {}",
+                    self.settings
+                        .logic
+                        .map_or(String::from("Any"), |logic| logic.to_string())
+                ),
+                Message::NewGameCycleLogic,
+            ))
+            .push(self.render_button(
+                String::from("question-marks-button"),
+                format!(
+                    "Question marks: {}",
+                    if self.settings.question_marks {
+                        "On"
+                    } else {
+                        "Off"
+                    }
+                ),
+                Message::ToggleQuestionMarks,
+            ))
+            .push(self.render_button(
+                String::from("resolve-forced-button"),
+                format!(
+                    "Resolve 50/50s: {}",
+                    if self.settings.resolve_forced_guesses {
+                        "On"
+                    } else {
+                        "Off"
+                    }
+                ),
+                Message::ToggleResolveForcedGuesses,
+            ))
+            .push(self.render_button(
+                String::from("daily-button"),
+                String::from("Daily"),
+                Message::OpenDaily,
+            ))
+            .push(self.render_button(
+                String::from("race-button"),
+                String::from("Race"),
+                Message::OpenLobby,
+            ))
+            .push(self.render_button(
+                String::from("preferences-button"),
+                String::from("Preferences"),
+                Message::OpenPreferences,
+            ));
+    }
+
+    fn render_lobby(&self) -> Column<'_, Message> {
+        let text = |content: String| Text::new(content).size(11).color(self.skin.palette.text);
+        let mut lobby = Column::new();
+        if let Some(status) = &self.race_status {
+            lobby = lobby.push(text(status.clone()));
+        }
+
+        let Some(race) = &self.race else {
+            return lobby
+                .push(
+                    text_input("host:port", &self.race_address)
+                        .on_input(Message::RaceAddressChanged)
+                        .size(11),
+                )
+                .push(self.render_button(
+                    String::from("host-button"),
+                    String::from("Host"),
+                    Message::HostRace,
+                ))
+                .push(self.render_button(
+                    String::from("join-button"),
+                    String::from("Join"),
+                    Message::JoinRace,
+                ))
+                .push(self.render_button(
+                    String::from("back-button"),
+                    String::from("Back"),
+                    Message::CloseLobby,
+                ));
+        };
+
+        lobby = lobby.push(text(String::from("Players:")));
+        for (&id, peer) in &race.peers {
+            let you = if id == race.client.id() { " (you)" } else { "" };
+            lobby = lobby.push(text(format!("{}{}", peer.name, you)));
+        }
+
+        if race.server.is_some() {
+            lobby = lobby.push(self.render_button(
+                String::from("start-race-button"),
+                format!("Start {:?}", self.difficulty),
+                Message::StartRace,
+            ));
+        } else {
+            lobby = lobby.push(text(String::from("Waiting for the host to start")));
+        }
+
+        return lobby
+            .push(self.render_button(
+                String::from("leave-button"),
+                String::from("Leave"),
+                Message::LeaveRace,
+            ))
+            .push(self.render_button(
+                String::from("back-button"),
+                String::from("Back"),
+                Message::CloseLobby,
+            ));
+    }
+
+    fn render_daily(&self) -> Column<'_, Message> {
+        const DIFFICULTIES: [GameDifficulty; 3] = [
+            GameDifficulty::Easy,
+            GameDifficulty::Medium,
+            GameDifficulty::Hard,
+        ];
+        const HISTORY_DAYS: i64 = 7;
+
+        let text = |content: String| Text::new(content).size(11).color(self.skin.palette.text);
+        let today = Date::today();
+        let mut menu = Column::new().push(text(format!("Daily {}", today)));
+
+        for difficulty in DIFFICULTIES {
+            let result = self.daily_history.get(today, difficulty);
+            let status = match result {
+                Some(result) if result.state == GameState::Win => {
+                    format!("{:.1}s", result.millis as f64 / 1000.0)
+                }
+                Some(_) => String::from("Lost"),
+                None => String::from("Play"),
+            };
+            menu = menu.push(self.render_button(
+                format!("daily-{:?}-button", difficulty),
+                format!("{:?}: {}", difficulty, status),
+                Message::DailyStart(difficulty),
+            ));
+            if result.is_some() {
+                menu = menu.push(self.render_button(
+                    format!("daily-copy-{:?}-button", difficulty),
+                    format!("Copy {:?} result", difficulty),
+                    Message::CopyDailyResult(difficulty),
+                ));
+            }
+        }
+
+        // Results of the last week, one line per day
+        menu = menu.push(text(String::from("History:")));
+        for days_ago in 0..HISTORY_DAYS {
+            let date = Date::from_days(today.days() - days_ago);
+            let results: Vec<String> = DIFFICULTIES
+                .iter()
+                .map(|&difficulty| {
+                    let initial = format!("{:?}", difficulty).remove(0);
+                    match self.daily_history.get(date, difficulty) {
+                        Some(result) if result.state == GameState::Win => {
+                            format!("{} {:.0}s", initial, result.millis as f64 / 1000.0)
+                        }
+                        Some(_) => format!("{} lost", initial),
+                        None => format!("{} -", initial),
+                    }
+                })
+                .collect();
+            menu = menu.push(text(format!(
+                "{:02}-{:02}  {}",
+                date.month,
+                date.day,
+                results.join("  ")
+            )));
+        }
+
+        return menu.push(self.render_button(
+            String::from("back-button"),
+            String::from("Back"),
+            Message::CloseDaily,
+        ));
+    }
+
+    /// List every move of the finished game with its verdict, mistakes highlighted
+    fn render_analysis(&self, review: &Review) -> Column<'_, Message> {
+        let palette = self.skin.palette;
+        let mut menu = Column::new().push(Text::new(review.summary()).size(11).color(palette.text));
+
+        for (i, move_review) in review.moves.iter().enumerate() {
+            let color = match move_review.verdict {
+                Verdict::Certain => palette.text,
+                Verdict::ForcedGuess => Color::from_rgb8(0xc0, 0x80, 0x00),
+                Verdict::AvoidableGuess => Color::from_rgb8(0xd0, 0x20, 0x20),
+            };
+            menu = menu.push(
+                Text::new(format!("{}. {}", i + 1, move_review))
+                    .size(11)
+                    .color(color),
+            );
+        }
+
+        return menu.push(self.render_button(
+            String::from("back-button"),
+            String::from("Back"),
+            Message::CloseAnalysis,
+        ));
+    }
+
+    /// Whether the boards of other racers are shown below the game board
+    fn shows_minimaps(&self) -> bool {
+        return self.race.as_ref().is_some_and(|race| {
+            race.started.is_some() && race.peers.keys().any(|&id| id != race.client.id())
+        });
+    }
+
+    /// Render a small map of every other racer's board with their result
+    fn render_minimaps(&self) -> Element<'_, Message> {
+        let mut minimaps = Row::new()
+            .spacing(Self::EDGE_PADDING)
+            .padding(Self::BORDER_PADDING * 2);
+        let Some(race) = &self.race else {
+            return minimaps.into();
+        };
+
+        for (&id, peer) in race.peers.iter().filter(|(&id, _)| id != race.client.id()) {
+            let status = match peer.result {
+                Some((GameState::Win, millis)) => {
+                    let verb = if race.winner == Some(id) {
+                        "won"
+                    } else {
+                        "finished"
+                    };
+                    format!(" {} in {:.1}s", verb, millis as f64 / 1000.0)
+                }
+                Some(_) => String::from(" lost"),
+                None => String::new(),
+            };
+
+            let mut map = Column::new();
+            for y in 0..self.game.height {
+                let row: Vec<char> = peer
+                    .board
+                    .get(usize::from(y))
+                    .map_or(Vec::new(), |row| row.chars().collect());
+                let mut map_row = Row::new();
+                for x in 0..self.game.width {
+                    let field = row.get(usize::from(x)).copied().unwrap_or('#');
+                    map_row = map_row.push(
+                        Container::new(Space::new(
+                            Self::MINIMAP_FIELD_SIZE,
+                            Self::MINIMAP_FIELD_SIZE,
+                        ))
+                        .style(ContainerStyles::minimap_field(self.minimap_color(field))),
+                    );
+                }
+                map = map.push(map_row);
+            }
+
+            minimaps = minimaps.push(
+                Column::new()
+                    .push(
+                        Text::new(format!("{}{}", peer.name, status))
+                            .size(Self::MINIMAP_TEXT_SIZE)
+                            .color(self.skin.palette.text),
+                    )
+                    .push(map)
+                    .spacing(Self::BORDER_PADDING),
+            );
+        }
+
+        return minimaps.into();
+    }
+
+    /// Colour of a field on the minimap, given its character in the race protocol
+    fn minimap_color(&self, field: char) -> Color {
+        match field {
+            '#' | '?' => self.skin.palette.shadow,
+            'F' => self.players[0].color,
+            '*' | 'M' => Color::BLACK,
+            _ => self.skin.palette.background,
+        }
+    }
+
+    fn render_preferences(&self) -> Column<'_, Message> {
+        let on_off = |enabled: bool| if enabled { "On" } else { "Off" };
+
+        return Column::new()
+            .push(self.render_button(
+                String::from("first-click-button"),
+                format!("First click: {}", self.settings.first_click),
+                Message::CycleFirstClick,
+            ))
+            .push(self.render_button(
+                String::from("solver-button"),
+                format!("Solver: {}", self.settings.solver_strategy),
+                Message::CycleSolverStrategy,
+            ))
+            .push(self.render_button(
+                String::from("mark-forced-button"),
+                format!("Mark 50/50s: {}", on_off(self.settings.mark_forced_guesses)),
+                Message::ToggleMarkForcedGuesses,
+            ))
+            .push(self.render_button(
+                String::from("skin-button"),
+                format!("Skin: {}", self.skin.name),
+                Message::CycleSkin,
+            ))
+            .push(self.render_button(
+                String::from("zoom-button"),
+                format!("Zoom: {}", self.settings.zoom),
+                Message::CycleZoom,
+            ))
+            .push(self.render_button(
+                String::from("sound-button"),
+                // Say why there is no sound rather than offer a setting that does nothing
+                format!(
+                    "Sound: {}",
+                    self.sounds
+                        .unavailable_reason()
+                        .unwrap_or(on_off(!self.sounds.is_muted()))
+                ),
+                Message::ToggleSound,
+            ))
+            .push(self.render_button(
+                String::from("volume-button"),
+                format!("Volume: {:.0}%", self.sounds.volume() * 100.0),
+                Message::CycleVolume,
+            ))
+            .push(self.render_button(
+                String::from("log-level-button"),
+                format!("Log level: {}", self.settings.log_level),
+                Message::CycleLogLevel,
+            ))
+            .push(self.render_button(
+                String::from("back-button"),
+                String::from("Back"),
+                Message::ClosePreferences,
+            ));
+    }
+
+    fn render_button(&self, id: String, text: String, message: Message) -> Element<'_, Message> {
         let mut button_container_style: Box<dyn Fn(&Theme) -> container::Style> =
-            Box::new(ContainerStyles::button_container);
-        let mut button_container_top_left_style: Box<dyn Fn(&Theme) -> container::Style> =
-            Box::new(ContainerStyles::button_container_top_left);
+            Box::new(ContainerStyles::button_container(self.skin.palette));
+        let mut button_container_top_left_style: Box<dyn Fn(&Theme) -> container::Style> = Box::new(
+            ContainerStyles::button_container_top_left(self.skin.palette),
+        );
         let mut button_container_bottom_right_style: Box<dyn Fn(&Theme) -> container::Style> =
-            Box::new(ContainerStyles::button_container_bottom_right);
+            Box::new(ContainerStyles::button_container_bottom_right(
+                self.skin.palette,
+            ));
 
         // Change style if button pressed
         if self.pressed_button_id == Some(id.clone()) {
-            button_container_style = Box::new(ContainerStyles::button_container_pressed);
-            button_container_top_left_style =
-                Box::new(ContainerStyles::button_container_top_left_pressed);
-            button_container_bottom_right_style =
-                Box::new(ContainerStyles::button_container_bottom_right_pressed);
+            button_container_style =
+                Box::new(ContainerStyles::button_container_pressed(self.skin.palette));
+            button_container_top_left_style = Box::new(
+                ContainerStyles::button_container_top_left_pressed(self.skin.palette),
+            );
+            button_container_bottom_right_style = Box::new(
+                ContainerStyles::button_container_bottom_right_pressed(self.skin.palette),
+            );
         }
 
         return MouseArea::new(
@@ -410,30 +1696,47 @@ impl MinesweeperInterface {
         return Container::new(
             Container::new(
                 Container::new(content)
-                    .style(ContainerStyles::wrapper_container)
+                    .style(ContainerStyles::wrapper_container(self.skin.palette))
                     .center(Length::Shrink),
             )
-            .style(ContainerStyles::wrapper_container_top_left)
+            .style(ContainerStyles::wrapper_container_top_left(
+                self.skin.palette,
+            ))
             .padding(padding::left(Self::BORDER_PADDING).top(Self::BORDER_PADDING))
             .center(Length::Shrink),
         )
-        .style(ContainerStyles::wrapper_container_bottom_right)
+        .style(ContainerStyles::wrapper_container_bottom_right(
+            self.skin.palette,
+        ))
         .padding(padding::bottom(Self::BORDER_PADDING).right(Self::BORDER_PADDING))
         .center(Length::Shrink)
         .into();
     }
 
-    fn render_remaining_mines_count(&self) -> Element<Message> {
+    fn render_remaining_mines_count(&self) -> Element<'_, Message> {
         let mine_count = self.game.remaining_mines();
+        let mut counters = Row::new().push(self.render_seven_seg_signed(mine_count, 3));
 
-        return self
-            .render_seven_seg_number(mine_count, 3)
+        // Show the remaining lives next to the mine counter
+        if self.game.has_lives() {
+            counters = counters
+                .push(self.render_seven_seg_number(self.game.lives().into(), 1))
+                .spacing(Self::BORDER_PADDING * 2);
+        }
+
+        return Column::new()
+            .push(counters)
             .align_x(Alignment::Start)
             .width(Length::FillPortion(1))
             .into();
     }
 
-    fn render_timer(&self) -> Element<Message> {
+    fn render_timer(&self) -> Element<'_, Message> {
+        // Two player games show the scores instead
+        if self.game.is_two_player() {
+            return self.render_scores();
+        }
+
         return self
             .render_seven_seg_number(self.timer, 3)
             .align_x(Alignment::End)
@@ -441,8 +1744,33 @@ impl MinesweeperInterface {
             .into();
     }
 
+    /// Render the mines found by each player, outlining the player whose turn it is
+    fn render_scores(&self) -> Element<'_, Message> {
+        let mut scores = Row::new().spacing(Self::BORDER_PADDING);
+        for (player, &score) in self.game.scores().iter().enumerate() {
+            let color = if player == self.game.current_player()
+                && self.game.game_state == GameState::InProgress
+            {
+                self.players[player].color
+            } else {
+                Color::TRANSPARENT
+            };
+            scores = scores.push(
+                Container::new(self.render_seven_seg_number(score, 2))
+                    .padding(Self::BORDER_PADDING)
+                    .style(ContainerStyles::player_outline(color)),
+            );
+        }
+
+        return Column::new()
+            .push(scores)
+            .align_x(Alignment::End)
+            .width(Length::FillPortion(1))
+            .into();
+    }
+
     /// Render the given number in seven segment digits (with padding to min_length)
-    fn render_seven_seg_number(&self, number: usize, min_length: usize) -> Column<Message> {
+    fn render_seven_seg_number(&self, number: usize, min_length: usize) -> Column<'_, Message> {
         // Create string and pad to the minimum length
         let mut number_string = number.to_string();
         while number_string.len() < min_length {
@@ -452,23 +1780,38 @@ impl MinesweeperInterface {
         return self.render_seven_seg_string(number_string);
     }
 
+    /// Render the given number in seven segment digits, with a leading dash when negative
+    fn render_seven_seg_signed(&self, number: isize, min_length: usize) -> Column<'_, Message> {
+        if number >= 0 {
+            return self.render_seven_seg_number(number.unsigned_abs(), min_length);
+        }
+
+        // The dash takes the place of the first digit
+        let mut number_string = number.unsigned_abs().to_string();
+        while number_string.len() + 1 < min_length {
+            number_string = String::from("0") + &number_string;
+        }
+
+        return self.render_seven_seg_string(String::from("-") + &number_string);
+    }
+
     /// Render the given string in seven segment digits
-    fn render_seven_seg_string(&self, str: String) -> Column<Message> {
+    fn render_seven_seg_string(&self, str: String) -> Column<'_, Message> {
         let mut row = Row::new();
-        for (_, c) in str.chars().enumerate() {
+        for c in str.chars() {
             let image: Image = match c {
-                '0' => image(&self.assets.score0),
-                '1' => image(&self.assets.score1),
-                '2' => image(&self.assets.score2),
-                '3' => image(&self.assets.score3),
-                '4' => image(&self.assets.score4),
-                '5' => image(&self.assets.score5),
-                '6' => image(&self.assets.score6),
-                '7' => image(&self.assets.score7),
-                '8' => image(&self.assets.score8),
-                '9' => image(&self.assets.score9),
-                '-' => image(&self.assets.score_dash),
-                _ => image(&self.assets.score_empty),
+                '0' => image(&self.skin.assets.score0),
+                '1' => image(&self.skin.assets.score1),
+                '2' => image(&self.skin.assets.score2),
+                '3' => image(&self.skin.assets.score3),
+                '4' => image(&self.skin.assets.score4),
+                '5' => image(&self.skin.assets.score5),
+                '6' => image(&self.skin.assets.score6),
+                '7' => image(&self.skin.assets.score7),
+                '8' => image(&self.skin.assets.score8),
+                '9' => image(&self.skin.assets.score9),
+                '-' => image(&self.skin.assets.score_dash),
+                _ => image(&self.skin.assets.score_empty),
             };
             row = row.push(Container::new(image))
         }
@@ -476,19 +1819,19 @@ impl MinesweeperInterface {
         return Column::new().push(row);
     }
 
-    fn render_face(&self) -> Element<Message> {
+    fn render_face(&self) -> Element<'_, Message> {
         // Get face image based on current game state
         let mut face_image = match self.game.game_state {
-            GameState::InProgress => image(&self.assets.face),
-            GameState::Loss => image(&self.assets.face_lose),
-            GameState::Win => image(&self.assets.face_win),
+            GameState::InProgress => image(&self.skin.assets.face),
+            GameState::Loss => image(&self.skin.assets.face_lose),
+            GameState::Win => image(&self.skin.assets.face_win),
         };
 
         // Override image if currently pressed down
         if self.face_pressed {
-            face_image = image(&self.assets.face_pressed);
+            face_image = image(&self.skin.assets.face_pressed);
         } else if self.open_pressed {
-            face_image = image(&self.assets.face_open);
+            face_image = image(&self.skin.assets.face_open);
         }
 
         // Create mouse area with interaction logic
@@ -505,34 +1848,46 @@ impl MinesweeperInterface {
             .into();
     }
 
-    fn render_field(&self, x: u16, y: u16) -> Element<Message> {
+    fn render_field(&self, x: u16, y: u16) -> Element<'_, Message> {
         let pos = (x, y);
         let field_state = self.game.get_field_state(pos);
 
         // Get the field content
         let cell_content: Element<Message> = match field_state {
-            FieldState::Unknown => image(&self.assets.closed).into(),
-            FieldState::Flagged => image(&self.assets.flag).into(),
-            FieldState::Question => image(&self.assets.question_closed).into(),
-            FieldState::MineRevealed => image(&self.assets.mine).into(),
-            FieldState::NoMine => image(&self.assets.mine_false).into(),
-            FieldState::MineDetonated => image(&self.assets.mine_detonated).into(),
+            FieldState::Unknown => image(&self.skin.assets.closed).into(),
+            FieldState::Flagged => image(&self.skin.assets.flag).into(),
+            FieldState::Question => image(&self.skin.assets.question_closed).into(),
+            FieldState::MineRevealed => image(&self.skin.assets.mine).into(),
+            FieldState::NoMine => image(&self.skin.assets.mine_false).into(),
+            FieldState::MineDetonated => image(&self.skin.assets.mine_detonated).into(),
+            FieldState::Claimed(player) => Container::new(image(&self.skin.assets.flag))
+                .style(ContainerStyles::player_outline(self.players[player].color))
+                .into(),
             FieldState::Open(count) => match count {
-                0 => image(&self.assets.field0).into(),
-                1 => image(&self.assets.field1).into(),
-                2 => image(&self.assets.field2).into(),
-                3 => image(&self.assets.field3).into(),
-                4 => image(&self.assets.field4).into(),
-                5 => image(&self.assets.field5).into(),
-                6 => image(&self.assets.field6).into(),
-                7 => image(&self.assets.field7).into(),
-                8 => image(&self.assets.field8).into(),
+                0 => image(&self.skin.assets.field0).into(),
+                1 => image(&self.skin.assets.field1).into(),
+                2 => image(&self.skin.assets.field2).into(),
+                3 => image(&self.skin.assets.field3).into(),
+                4 => image(&self.skin.assets.field4).into(),
+                5 => image(&self.skin.assets.field5).into(),
+                6 => image(&self.skin.assets.field6).into(),
+                7 => image(&self.skin.assets.field7).into(),
+                8 => image(&self.skin.assets.field8).into(),
                 _ => panic!("Mine count out of range 0 - 8"),
             },
         };
 
+        // Outline closed fields that need a guess no other move can avoid
+        let mut cell = Container::new(cell_content);
+        if self.settings.mark_forced_guesses
+            && matches!(field_state, FieldState::Unknown | FieldState::Question)
+            && self.solver.forced_guess(pos).is_some()
+        {
+            cell = cell.style(ContainerStyles::player_outline(Self::FORCED_GUESS_COLOR));
+        }
+
         // Create field content
-        let field: Element<Message> = MouseArea::new(Container::new(cell_content))
+        let field: Element<Message> = MouseArea::new(cell)
             .on_press(Message::OpenPressed)
             .on_right_press(Message::OpenPressed)
             .on_exit(Message::OpenReleased)
@@ -541,8 +1896,8 @@ impl MinesweeperInterface {
             .interaction(mouse::Interaction::Pointer)
             .into();
 
-        // Add tooltip if mine chance enabled
-        if self.show_mine_chance {
+        // Add tooltip if mine chance enabled, daily results are shared so they get no help
+        if self.show_mine_chance && self.daily.is_none() {
             let mine_chance = self.solver.get_mine_chance(pos);
             let mine_chance_string = format!("{}", mine_chance);
             return tooltip(
@@ -556,3 +1911,46 @@ impl MinesweeperInterface {
         return field;
     }
 }
+
+/// Find a rated board on another thread, so the window keeps responding while boards are played through
+async fn generate_board(
+    width: u16,
+    height: u16,
+    mines: usize,
+    logic: LogicLevel,
+) -> Result<RatedBoard, RatingError> {
+    let board =
+        in_background(move || rating::find_board(width, height, mines, logic, rand::random()));
+    return board.await.unwrap_or(Err(RatingError::Aborted));
+}
+
+/// Run blocking work on another thread, none if the thread panicked before finishing
+async fn in_background<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Option<T> {
+    let (sender, receiver) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(work());
+    });
+    return receiver.await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_background_test() {
+        use iced::futures::executor::block_on;
+
+        assert_eq!(block_on(in_background(|| 1 + 1)), Some(2));
+
+        // A panic on the worker thread is reported rather than taking the window down with it
+        assert_eq!(
+            block_on(in_background(|| -> u8 { panic!("Worker failed") })),
+            None
+        );
+        assert_eq!(
+            block_on(generate_board(4, 4, 1, LogicLevel::Hard)),
+            Err(RatingError::NotFound(LogicLevel::Hard))
+        );
+    }
+}
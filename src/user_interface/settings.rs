@@ -0,0 +1,205 @@
+use std::{fmt::Display, fs, path::PathBuf};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use super::{skin::Skin, GameDifficulty};
+use crate::{
+    minesweeper::FirstClick,
+    solver::{rating::LogicLevel, SolverStrategy},
+};
+
+/// How much the interface is scaled up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Zoom {
+    /// Fixed whole number scale, the window is resized to fit the board
+    Scale(u16),
+    /// Scale the board to fill the window
+    FitToWindow,
+}
+
+impl Zoom {
+    pub const MIN_SCALE: u16 = 1;
+    pub const MAX_SCALE: u16 = 4;
+
+    /// Get the fixed scale, or the default scale when fitting to the window
+    pub fn scale(&self) -> u16 {
+        match self {
+            Zoom::Scale(scale) => *scale,
+            Zoom::FitToWindow => 2,
+        }
+    }
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Zoom::Scale(2)
+    }
+}
+
+impl Display for Zoom {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Zoom::Scale(scale) => write!(f, "{}x", scale),
+            Zoom::FitToWindow => f.write_str("Fit"),
+        }
+    }
+}
+
+/// User preferences, persisted between launches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Difficulty of the last game started
+    pub difficulty: GameDifficulty,
+    /// Lives for new games
+    pub lives: u8,
+    /// Number of players for new games, two players take turns finding mines
+    pub players: u8,
+    /// Class of board for new games, any board when unset
+    pub logic: Option<LogicLevel>,
+    pub zoom: Zoom,
+    /// Name of the selected skin
+    pub skin: String,
+    /// Whether flagging cycles through question marks
+    pub question_marks: bool,
+    pub first_click: FirstClick,
+    pub sound: bool,
+    pub volume: f32,
+    pub solver_strategy: SolverStrategy,
+    /// Whether fields that need a guess no move can avoid are outlined
+    pub mark_forced_guesses: bool,
+    /// Whether opening such a field in a normal game always finds it safe
+    pub resolve_forced_guesses: bool,
+    pub log_level: LevelFilter,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            difficulty: GameDifficulty::Easy,
+            lives: 1,
+            players: 1,
+            logic: None,
+            zoom: Zoom::default(),
+            skin: String::from(Skin::CLASSIC),
+            question_marks: true,
+            first_click: FirstClick::Safe,
+            sound: true,
+            volume: 0.5,
+            solver_strategy: SolverStrategy::default(),
+            mark_forced_guesses: false,
+            resolve_forced_guesses: false,
+            log_level: LevelFilter::Info,
+        }
+    }
+}
+
+impl Settings {
+    /// Location of the settings file in the user's config directory
+    pub fn path() -> Option<PathBuf> {
+        return dirs::config_dir().map(|dir| dir.join("minesweeper").join("settings.toml"));
+    }
+
+    /// Load the settings file, using the defaults if it is missing or invalid
+    pub fn load() -> Settings {
+        let Some(path) = Self::path() else {
+            return Settings::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Settings::default();
+        };
+
+        match toml::from_str(&contents) {
+            Ok(settings) => settings,
+            Err(error) => {
+                log::warn!(
+                    "Invalid settings file '{}', using defaults: {}",
+                    path.display(),
+                    error
+                );
+                Settings::default()
+            }
+        }
+    }
+
+    /// Write the settings file, logging any failure
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            log::warn!("No config directory, settings will not be saved");
+            return;
+        };
+
+        let result = toml::to_string(self)
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                fs::write(&path, contents).map_err(|error| error.to_string())
+            });
+
+        match result {
+            Ok(()) => log::debug!("Saved settings to '{}'", path.display()),
+            Err(error) => log::warn!("Could not save settings to '{}': {}", path.display(), error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_round_trip_test() {
+        for zoom in [Zoom::Scale(3), Zoom::FitToWindow] {
+            let settings = Settings {
+                zoom,
+                ..Default::default()
+            };
+            let contents = toml::to_string(&settings).unwrap();
+            let loaded: Settings = toml::from_str(&contents).unwrap();
+            assert_eq!(loaded.zoom, zoom);
+        }
+
+        // Missing values fall back to the defaults
+        let loaded: Settings = toml::from_str("").unwrap();
+        assert_eq!(loaded.zoom, Zoom::default());
+    }
+
+    #[test]
+    fn settings_round_trip_test() {
+        let settings = Settings {
+            difficulty: GameDifficulty::Hard,
+            lives: 3,
+            logic: Some(LogicLevel::Hard),
+            skin: String::from("Custom"),
+            question_marks: false,
+            first_click: FirstClick::Opening,
+            sound: false,
+            volume: 0.25,
+            solver_strategy: SolverStrategy::SafeOnly,
+            mark_forced_guesses: true,
+            resolve_forced_guesses: true,
+            log_level: LevelFilter::Debug,
+            ..Default::default()
+        };
+        let contents = toml::to_string(&settings).unwrap();
+        let loaded: Settings = toml::from_str(&contents).unwrap();
+        assert_eq!(loaded.difficulty, GameDifficulty::Hard);
+        assert_eq!(loaded.lives, 3);
+        assert_eq!(loaded.logic, Some(LogicLevel::Hard));
+        assert_eq!(loaded.skin, "Custom");
+        assert!(!loaded.question_marks);
+        assert_eq!(loaded.first_click, FirstClick::Opening);
+        assert!(!loaded.sound);
+        assert_eq!(loaded.volume, 0.25);
+        assert_eq!(loaded.solver_strategy, SolverStrategy::SafeOnly);
+        assert!(loaded.mark_forced_guesses && loaded.resolve_forced_guesses);
+        assert_eq!(loaded.log_level, LevelFilter::Debug);
+
+        // Unknown values are rejected rather than silently changed
+        assert!(toml::from_str::<Settings>("first_click = \"sometimes\"").is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use iced::{widget::image, Color};
+use serde::Deserialize;
+
+use super::assets::MinesweeperAssets;
+
+/// Colours used to draw the interface around the tiles
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// Background of the window and buttons
+    pub background: Color,
+    /// Background behind the board and counters
+    pub inset: Color,
+    /// Light edge of bevelled borders
+    pub highlight: Color,
+    /// Dark edge of bevelled borders, also used for pressed buttons
+    pub shadow: Color,
+    pub text: Color,
+}
+
+impl Palette {
+    /// Grey windows 95 style palette
+    pub fn classic() -> Palette {
+        Palette {
+            background: Color::from_rgb8(191, 191, 191),
+            inset: Color::from_rgb8(76, 76, 76),
+            highlight: Color::from_rgb8(255, 255, 255),
+            shadow: Color::from_rgb8(127, 127, 127),
+            text: Color::from_rgb8(0, 0, 0),
+        }
+    }
+
+    pub fn dark() -> Palette {
+        Palette {
+            background: Color::from_rgb8(48, 48, 52),
+            inset: Color::from_rgb8(20, 20, 22),
+            highlight: Color::from_rgb8(92, 92, 98),
+            shadow: Color::from_rgb8(28, 28, 30),
+            text: Color::from_rgb8(220, 220, 220),
+        }
+    }
+
+    /// Set the colour with the given manifest name
+    fn set(&mut self, name: &str, color: Color) -> Result<(), SkinError> {
+        match name {
+            "background" => self.background = color,
+            "inset" => self.inset = color,
+            "highlight" => self.highlight = color,
+            "shadow" => self.shadow = color,
+            "text" => self.text = color,
+            _ => return Err(SkinError::UnknownColor(name.to_string())),
+        }
+        return Ok(());
+    }
+}
+
+/// Errors that can occur while loading a skin
+#[derive(Debug)]
+pub enum SkinError {
+    Io(PathBuf, std::io::Error),
+    Manifest(toml::de::Error),
+    Image(PathBuf, ::image::ImageError),
+    UnknownTile(String),
+    UnknownColor(String),
+    InvalidColor(String),
+    /// A tile is given as a rectangle, but the manifest has no sprite sheet
+    MissingSheet(String),
+    /// A tile rectangle does not fit inside the sprite sheet
+    OutOfSheet(String),
+}
+
+impl Display for SkinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkinError::Io(path, error) => {
+                write!(f, "Could not read '{}': {}", path.display(), error)
+            }
+            SkinError::Manifest(error) => write!(f, "Invalid manifest: {}", error),
+            SkinError::Image(path, error) => {
+                write!(f, "Invalid image '{}': {}", path.display(), error)
+            }
+            SkinError::UnknownTile(name) => write!(f, "Unknown tile '{}'", name),
+            SkinError::UnknownColor(name) => write!(f, "Unknown palette colour '{}'", name),
+            SkinError::InvalidColor(value) => write!(f, "Invalid colour '{}'", value),
+            SkinError::MissingSheet(name) => {
+                write!(
+                    f,
+                    "Tile '{}' is a sprite sheet rectangle, but no sheet is set",
+                    name
+                )
+            }
+            SkinError::OutOfSheet(name) => write!(f, "Tile '{}' is outside the sprite sheet", name),
+        }
+    }
+}
+
+impl std::error::Error for SkinError {}
+
+/// Where a tile image comes from in a skin manifest
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TileSource {
+    /// Image file relative to the skin directory
+    File(PathBuf),
+    /// `[x, y, width, height]` rectangle in the sprite sheet
+    Sheet([u32; 4]),
+}
+
+/// Contents of a `skin.toml` manifest
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    sheet: Option<PathBuf>,
+    #[serde(default)]
+    tiles: HashMap<String, TileSource>,
+    #[serde(default)]
+    palette: HashMap<String, String>,
+}
+
+/// Tile images and interface colours used to draw the game
+///
+/// Anything a skin does not set is taken from the built-in classic skin
+#[derive(Debug)]
+pub struct Skin {
+    pub name: String,
+    pub assets: MinesweeperAssets,
+    pub palette: Palette,
+}
+
+impl Default for Skin {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl Skin {
+    pub const CLASSIC: &'static str = "Classic";
+    pub const DARK: &'static str = "Dark";
+    const MANIFEST: &'static str = "skin.toml";
+
+    /// Built-in skin using the embedded tiles
+    pub fn classic() -> Skin {
+        Skin {
+            name: String::from(Self::CLASSIC),
+            assets: Default::default(),
+            palette: Palette::classic(),
+        }
+    }
+
+    /// Built-in dark skin, the embedded tiles with their greys darkened
+    pub fn dark() -> Skin {
+        let mut assets = MinesweeperAssets::default();
+        for name in MinesweeperAssets::TILE_NAMES {
+            if let Some(tile) = assets.tile_mut(name) {
+                if let Some(darkened) = darken(tile) {
+                    *tile = darkened;
+                }
+            }
+        }
+
+        Skin {
+            name: String::from(Self::DARK),
+            assets,
+            palette: Palette::dark(),
+        }
+    }
+
+    /// Directory searched for user skins, each skin is a sub-directory containing a `skin.toml`
+    pub fn skins_dir() -> Option<PathBuf> {
+        return dirs::config_dir().map(|dir| dir.join("minesweeper").join("skins"));
+    }
+
+    /// Names of every skin that can be selected, built-in skins first
+    pub fn available() -> Vec<String> {
+        let mut names = vec![String::from(Self::CLASSIC), String::from(Self::DARK)];
+
+        let entries = Self::skins_dir().and_then(|dir| fs::read_dir(dir).ok());
+        let mut user_skins: Vec<String> = entries
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().join(Self::MANIFEST).is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| !names.contains(name))
+            .collect();
+        user_skins.sort();
+        names.append(&mut user_skins);
+
+        return names;
+    }
+
+    /// Get the skin with the given name, falling back to the classic skin if it cannot be loaded
+    pub fn by_name(name: &str) -> Skin {
+        if name == Self::CLASSIC {
+            return Self::classic();
+        }
+        if name == Self::DARK {
+            return Self::dark();
+        }
+
+        let Some(dir) = Self::skins_dir() else {
+            log::warn!("No config directory, using the classic skin");
+            return Self::classic();
+        };
+
+        match Self::load(&dir.join(name)) {
+            Ok(skin) => skin,
+            Err(error) => {
+                log::warn!(
+                    "Could not load skin '{}', using the classic skin: {}",
+                    name,
+                    error
+                );
+                Self::classic()
+            }
+        }
+    }
+
+    /// Load the skin in the given directory
+    pub fn load(dir: &Path) -> Result<Skin, SkinError> {
+        let manifest_path = dir.join(Self::MANIFEST);
+        let manifest = fs::read_to_string(&manifest_path)
+            .map_err(|error| SkinError::Io(manifest_path, error))?;
+        let manifest: Manifest = toml::from_str(&manifest).map_err(SkinError::Manifest)?;
+
+        // Skins are named after their directory
+        let mut skin = Self::classic();
+        skin.name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let sheet = match &manifest.sheet {
+            Some(sheet) => Some(read_image(&dir.join(sheet))?.0.to_rgba8()),
+            None => None,
+        };
+
+        for (name, source) in &manifest.tiles {
+            let tile = skin
+                .assets
+                .tile_mut(name)
+                .ok_or_else(|| SkinError::UnknownTile(name.clone()))?;
+
+            *tile = match source {
+                TileSource::File(path) => image::Handle::from_bytes(read_image(&dir.join(path))?.1),
+                TileSource::Sheet([x, y, width, height]) => {
+                    let sheet = sheet
+                        .as_ref()
+                        .ok_or_else(|| SkinError::MissingSheet(name.clone()))?;
+                    // Checked so a tile near the largest coordinate is an error rather than an overflow
+                    if x.checked_add(*width).is_none_or(|end| end > sheet.width())
+                        || y.checked_add(*height)
+                            .is_none_or(|end| end > sheet.height())
+                    {
+                        return Err(SkinError::OutOfSheet(name.clone()));
+                    }
+
+                    let pixels = ::image::imageops::crop_imm(sheet, *x, *y, *width, *height);
+                    image::Handle::from_rgba(*width, *height, pixels.to_image().into_raw())
+                }
+            };
+        }
+
+        for (name, value) in &manifest.palette {
+            let color =
+                Color::parse(value).ok_or_else(|| SkinError::InvalidColor(value.clone()))?;
+            skin.palette.set(name, color)?;
+        }
+
+        return Ok(skin);
+    }
+}
+
+/// Read and decode the image at the given path, returning the raw file as well
+fn read_image(path: &Path) -> Result<(::image::DynamicImage, Vec<u8>), SkinError> {
+    let bytes = fs::read(path).map_err(|error| SkinError::Io(path.to_path_buf(), error))?;
+    let decoded = ::image::load_from_memory(&bytes)
+        .map_err(|error| SkinError::Image(path.to_path_buf(), error))?;
+    return Ok((decoded, bytes));
+}
+
+/// Darken the grey pixels of an embedded tile, leaving coloured pixels untouched
+fn darken(tile: &image::Handle) -> Option<image::Handle> {
+    let image::Handle::Bytes(_, bytes) = tile else {
+        return None;
+    };
+    let mut pixels = ::image::load_from_memory(bytes).ok()?.to_rgba8();
+
+    for pixel in pixels.pixels_mut() {
+        let [r, g, b, _] = &mut pixel.0;
+        if *r == *g && *g == *b {
+            let value = (f32::from(*r) * 0.35) as u8;
+            (*r, *g, *b) = (value, value, value);
+        }
+    }
+
+    return Some(image::Handle::from_rgba(
+        pixels.width(),
+        pixels.height(),
+        pixels.into_raw(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create an empty skin directory for a test
+    fn skin_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("minesweeper-skin-{}", test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        return dir;
+    }
+
+    #[test]
+    fn sprite_sheet_test() {
+        let dir = skin_dir("sheet");
+        ::image::RgbaImage::from_pixel(32, 16, ::image::Rgba([255, 0, 0, 255]))
+            .save(dir.join("sheet.png"))
+            .unwrap();
+        fs::write(
+            dir.join("skin.toml"),
+            r##"
+                sheet = "sheet.png"
+
+                [tiles]
+                closed = [0, 0, 16, 16]
+                flag = [16, 0, 16, 16]
+
+                [palette]
+                background = "#102030"
+            "##,
+        )
+        .unwrap();
+
+        let mut skin = Skin::load(&dir).unwrap();
+        assert_eq!(skin.name, "minesweeper-skin-sheet");
+        assert_eq!(skin.palette.background, Color::from_rgb8(16, 32, 48));
+        assert_eq!(skin.palette.text, Palette::classic().text);
+        assert!(matches!(
+            skin.assets.tile_mut("flag"),
+            Some(image::Handle::Rgba { width: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_skin_test() {
+        let dir = skin_dir("invalid");
+        fs::write(dir.join("skin.toml"), "[tiles]\nclosed = [0, 0, 16, 16]\n").unwrap();
+        assert!(matches!(
+            Skin::load(&dir),
+            Err(SkinError::MissingSheet(name)) if name == "closed"
+        ));
+
+        fs::write(dir.join("skin.toml"), "[tiles]\nsmiley = \"face.png\"\n").unwrap();
+        assert!(matches!(Skin::load(&dir), Err(SkinError::UnknownTile(_))));
+
+        ::image::RgbaImage::new(16, 16)
+            .save(dir.join("sheet.png"))
+            .unwrap();
+        for tile in [
+            "[16, 0, 16, 16]",
+            "[4294967295, 0, 16, 16]",
+            "[0, 1, 16, 4294967295]",
+        ] {
+            fs::write(
+                dir.join("skin.toml"),
+                format!("sheet = \"sheet.png\"\n[tiles]\nclosed = {}\n", tile),
+            )
+            .unwrap();
+            assert!(
+                matches!(Skin::load(&dir), Err(SkinError::OutOfSheet(name)) if name == "closed"),
+                "{}",
+                tile
+            );
+        }
+    }
+}
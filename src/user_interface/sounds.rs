@@ -0,0 +1,221 @@
+use std::fmt::Debug;
+
+use crate::minesweeper::GameEvent;
+
+/// Sound effects played in response to game events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Click,
+    Cascade,
+    Flag,
+    Detonation,
+    Win,
+}
+
+impl Sound {
+    /// Get the sound played for a game event, if any
+    pub fn for_event(event: &GameEvent) -> Option<Sound> {
+        match event {
+            GameEvent::Opened(_, _) => Some(Sound::Click),
+            GameEvent::Cascade(_) => Some(Sound::Cascade),
+            GameEvent::FlagPlaced(_) | GameEvent::MineClaimed(_, _) => Some(Sound::Flag),
+            GameEvent::MineDetonated(_) => Some(Sound::Detonation),
+            GameEvent::Won => Some(Sound::Win),
+            GameEvent::FlagRemoved(_)
+            | GameEvent::QuestionPlaced(_)
+            | GameEvent::QuestionRemoved(_)
+            | GameEvent::TurnChanged(_)
+            | GameEvent::Lost => None,
+        }
+    }
+}
+
+/// Sound effect data embedded in the executable
+#[derive(Debug)]
+pub struct MinesweeperSounds {
+    pub click: &'static [u8],
+    pub cascade: &'static [u8],
+    pub flag: &'static [u8],
+    pub detonation: &'static [u8],
+    pub win: &'static [u8],
+}
+impl Default for MinesweeperSounds {
+    fn default() -> Self {
+        Self {
+            click: include_bytes!("../../resources/sounds/click.wav"),
+            cascade: include_bytes!("../../resources/sounds/cascade.wav"),
+            flag: include_bytes!("../../resources/sounds/flag.wav"),
+            detonation: include_bytes!("../../resources/sounds/detonation.wav"),
+            win: include_bytes!("../../resources/sounds/win.wav"),
+        }
+    }
+}
+impl MinesweeperSounds {
+    /// Get the WAV data for the given sound
+    pub fn get(&self, sound: Sound) -> &'static [u8] {
+        match sound {
+            Sound::Click => self.click,
+            Sound::Cascade => self.cascade,
+            Sound::Flag => self.flag,
+            Sound::Detonation => self.detonation,
+            Sound::Win => self.win,
+        }
+    }
+}
+
+/// Plays sound effects
+///
+/// Audio output requires the `audio` feature, without it or without an audio device the player stays silent
+pub struct SoundPlayer {
+    #[cfg_attr(not(feature = "audio"), allow(dead_code))]
+    sounds: MinesweeperSounds,
+    volume: f32,
+    muted: bool,
+    #[cfg(feature = "audio")]
+    output: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+}
+
+impl Debug for SoundPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SoundPlayer")
+            .field("volume", &self.volume)
+            .field("muted", &self.muted)
+            .field("available", &self.is_available())
+            .finish()
+    }
+}
+
+impl Default for SoundPlayer {
+    fn default() -> Self {
+        #[cfg(not(feature = "audio"))]
+        log::info!("Built without the audio feature, sound is disabled");
+
+        Self {
+            sounds: Default::default(),
+            volume: 0.5,
+            muted: false,
+            #[cfg(feature = "audio")]
+            output: match rodio::OutputStream::try_default() {
+                Ok(output) => Some(output),
+                Err(error) => {
+                    log::warn!("No audio output available, sound disabled: {}", error);
+                    None
+                }
+            },
+        }
+    }
+}
+
+impl SoundPlayer {
+    /// Check if there is an audio output to play sounds on
+    pub fn is_available(&self) -> bool {
+        #[cfg(feature = "audio")]
+        return self.output.is_some();
+        #[cfg(not(feature = "audio"))]
+        return false;
+    }
+
+    /// Get why sounds cannot be played, none if they can
+    pub fn unavailable_reason(&self) -> Option<&'static str> {
+        if self.is_available() {
+            return None;
+        }
+        if cfg!(feature = "audio") {
+            return Some("No device");
+        }
+        return Some("Not built");
+    }
+
+    pub fn volume(&self) -> f32 {
+        return self.volume;
+    }
+
+    /// Set the volume, from 0 (silent) to 1 (full volume)
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        return self.muted;
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// Play the given sound without waiting for it to finish
+    pub fn play(&self, sound: Sound) {
+        if self.muted || self.volume <= 0.0 {
+            return;
+        }
+        log::trace!("Playing sound {:?}", sound);
+
+        #[cfg(feature = "audio")]
+        if let Some((_, handle)) = &self.output {
+            use rodio::Source;
+
+            let data = std::io::Cursor::new(self.sounds.get(sound));
+            let result = rodio::Decoder::new(data)
+                .map_err(rodio::PlayError::from)
+                .and_then(|source| {
+                    handle.play_raw(source.convert_samples::<f32>().amplify(self.volume))
+                });
+            if let Err(error) = result {
+                log::warn!("Could not play sound {:?}: {}", sound, error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_sound_test() {
+        assert_eq!(
+            Sound::for_event(&GameEvent::Opened((0, 0), 1)),
+            Some(Sound::Click)
+        );
+        assert_eq!(
+            Sound::for_event(&GameEvent::Cascade(vec![(0, 0), (1, 0)])),
+            Some(Sound::Cascade)
+        );
+        assert_eq!(
+            Sound::for_event(&GameEvent::FlagPlaced((0, 0))),
+            Some(Sound::Flag)
+        );
+        assert_eq!(
+            Sound::for_event(&GameEvent::MineClaimed((0, 0), 1)),
+            Some(Sound::Flag)
+        );
+        assert_eq!(
+            Sound::for_event(&GameEvent::MineDetonated((0, 0))),
+            Some(Sound::Detonation)
+        );
+        assert_eq!(Sound::for_event(&GameEvent::Won), Some(Sound::Win));
+        assert_eq!(Sound::for_event(&GameEvent::FlagRemoved((0, 0))), None);
+        assert_eq!(Sound::for_event(&GameEvent::TurnChanged(0)), None);
+        assert_eq!(Sound::for_event(&GameEvent::Lost), None);
+
+        // Every sound has WAV data embedded, whether or not it can be played
+        let sounds = MinesweeperSounds::default();
+        for sound in [
+            Sound::Click,
+            Sound::Cascade,
+            Sound::Flag,
+            Sound::Detonation,
+            Sound::Win,
+        ] {
+            assert!(sounds.get(sound).starts_with(b"RIFF"), "{:?}", sound);
+        }
+
+        // Playing without an output device, or without the audio feature, does nothing
+        let player = SoundPlayer::default();
+        player.play(Sound::Click);
+        if !cfg!(feature = "audio") {
+            assert!(!player.is_available());
+            assert_eq!(player.unavailable_reason(), Some("Not built"));
+        }
+    }
+}
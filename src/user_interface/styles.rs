@@ -1,12 +1,34 @@
-use iced::{widget::container, Background, Color, Theme};
+use iced::{widget::container, Background, Border, Color, Theme};
 
-/// Struct containing styles for [container]
+use super::skin::Palette;
+
+/// Struct containing styles for [container], coloured by the current skin's [Palette]
 pub struct ContainerStyles {}
 impl ContainerStyles {
+    /// Container style outlining something in a player's colour
+    pub fn player_outline(color: Color) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            border: Border {
+                color,
+                width: 2.0,
+                radius: 0.into(),
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Container style for a single field of a race minimap
+    pub fn minimap_field(color: Color) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(color)),
+            ..Default::default()
+        }
+    }
+
     /// Container style for the top level game container
-    pub fn game_container(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(191, 191, 191))),
+    pub fn game_container(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.background)),
             ..Default::default()
         }
     }
@@ -16,9 +38,9 @@ impl ContainerStyles {
     /// Surrounds element with borders similar to class minesweeper
     ///
     /// This style provides the background
-    pub fn wrapper_container(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(76, 76, 76))),
+    pub fn wrapper_container(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.inset)),
             ..Default::default()
         }
     }
@@ -28,9 +50,9 @@ impl ContainerStyles {
     /// Surrounds element with borders similar to class minesweeper
     ///
     /// This style provides the top left border
-    pub fn wrapper_container_top_left(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(127, 127, 127))),
+    pub fn wrapper_container_top_left(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.shadow)),
             ..Default::default()
         }
     }
@@ -40,18 +62,18 @@ impl ContainerStyles {
     /// Surrounds element with borders similar to class minesweeper
     ///
     /// This style provides the bottom right border
-    pub fn wrapper_container_bottom_right(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(255, 255, 255))),
+    pub fn wrapper_container_bottom_right(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.highlight)),
             ..Default::default()
         }
     }
 
     /// Container style for a windows 95 style bevelled button
-    pub fn button_container(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(191, 191, 191))),
-            text_color: Some(Color::from_rgb8(0, 0, 0)),
+    pub fn button_container(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.background)),
+            text_color: Some(palette.text),
             ..Default::default()
         }
     }
@@ -59,9 +81,9 @@ impl ContainerStyles {
     /// Container style for a windows 95 style bevelled button
     ///
     /// This is the top left border style
-    pub fn button_container_top_left(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(255, 255, 255))),
+    pub fn button_container_top_left(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.highlight)),
             ..Default::default()
         }
     }
@@ -69,18 +91,18 @@ impl ContainerStyles {
     /// Container style for a windows 95 style bevelled button
     ///
     /// This is the bottom right border style
-    pub fn button_container_bottom_right(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(127, 127, 127))),
+    pub fn button_container_bottom_right(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.shadow)),
             ..Default::default()
         }
     }
 
     /// Container style for a windows 95 style bevelled button being pressed
-    pub fn button_container_pressed(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(127, 127, 127))),
-            text_color: Some(Color::from_rgb8(0, 0, 0)),
+    pub fn button_container_pressed(palette: Palette) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.shadow)),
+            text_color: Some(palette.text),
             ..Default::default()
         }
     }
@@ -88,9 +110,11 @@ impl ContainerStyles {
     /// Container style for a windows 95 style bevelled button being pressed
     ///
     /// This is the top left border style
-    pub fn button_container_top_left_pressed(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(127, 127, 127))),
+    pub fn button_container_top_left_pressed(
+        palette: Palette,
+    ) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.shadow)),
             ..Default::default()
         }
     }
@@ -98,9 +122,11 @@ impl ContainerStyles {
     /// Container style for a windows 95 style bevelled button being pressed
     ///
     /// This is the bottom right border style
-    pub fn button_container_bottom_right_pressed(_: &Theme) -> container::Style {
-        container::Style {
-            background: Some(Background::Color(Color::from_rgb8(127, 127, 127))),
+    pub fn button_container_bottom_right_pressed(
+        palette: Palette,
+    ) -> impl Fn(&Theme) -> container::Style {
+        move |_| container::Style {
+            background: Some(Background::Color(palette.shadow)),
             ..Default::default()
         }
     }